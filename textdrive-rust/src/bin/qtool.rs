@@ -0,0 +1,309 @@
+use std::env;
+use std::process;
+use textdrive::encoder::Encoder;
+use textdrive::error::{GameError, Result};
+use textdrive::evaluation::{EvalSuite, DEFAULT_SUITE_COURSES, DEFAULT_SUITE_SEED};
+use textdrive::merge::{diff, merge, valid_weights, MergeMode};
+use textdrive::model::{migrate, ModelInfo, MODEL_VERSION};
+use textdrive::policy::load_policy;
+use textdrive::qlearning::{best_action, Action, Agent};
+
+const DEFAULT_DIFF_LIMIT: usize = 20;
+const DEFAULT_MAX_STEPS: usize = 10000;
+const SURVIVAL_POINTS: usize = 10;
+
+const USAGE: &str = "Usage: qtool inspect FILE [STATE]
+       qtool diff FILE_A FILE_B [--limit N]
+       qtool merge OUT FILE... [--mode average|max-confidence] [--weights W,W,...]
+       qtool evaluate FILE [--courses N] [--seed N] [--max-steps N]
+       qtool migrate FILE";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("inspect") => inspect(&args[1..]),
+        Some("diff") => run_diff(&args[1..]),
+        Some("merge") => run_merge(&args[1..]),
+        Some("evaluate") => evaluate(&args[1..]),
+        Some("migrate") => run_migrate(&args[1..]),
+        _ => Err(GameError::InvalidConfig("unknown command".to_string())),
+    };
+
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        if let GameError::InvalidConfig(_) = err {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+        process::exit(1);
+    }
+}
+
+fn inspect(args: &[String]) -> Result<()> {
+    let (file, state) = match args {
+        [file] => (file, None),
+        [file, state] => (file, Some(parse_number::<usize>("STATE", state)?)),
+        _ => return Err(GameError::InvalidConfig("inspect needs a file".to_string())),
+    };
+
+    let Some(state) = state else {
+        print_info(&ModelInfo::load(file)?);
+        return Ok(());
+    };
+
+    let agent = Agent::load(file)?;
+    if state >= agent.q.len() {
+        return Err(GameError::InvalidConfig(format!(
+            "state {} out of range (model has {} states)",
+            state,
+            agent.q.len()
+        )));
+    }
+
+    let q_values = agent.q_values(state);
+    let best = best_action(&q_values);
+    println!("State {} ({})", state, agent.encoder);
+    for action in Action::all() {
+        println!(
+            "  {:<6} {:10.3}{}",
+            format!("{:?}", action),
+            q_values[action as usize],
+            if action == best { "  <- best" } else { "" }
+        );
+    }
+    if let Some(visits) = agent.visit_count(state) {
+        println!("Visits: {}", visits);
+    }
+
+    if let Encoder::Window(window) = agent.encoder {
+        println!("\nWalls ahead:");
+        for (row, cells) in window.decode(state).iter().enumerate().rev() {
+            let line: Vec<&str> = cells
+                .iter()
+                .map(|&wall| if wall { "#" } else { "." })
+                .collect();
+            println!("  {}  {}", row + 1, line.join(" "));
+        }
+        let car: Vec<&str> = (0..2 * window.range + 1)
+            .map(|col| if col == window.range { "A" } else { " " })
+            .collect();
+        println!("     {}", car.join(" "));
+    }
+    Ok(())
+}
+
+fn print_info(info: &ModelInfo) {
+    println!("Format version:  {}", info.version);
+    println!("Encoder:         {}", info.encoder);
+    println!("Storage:         {}", info.storage);
+    println!("States:          {} ({} stored)", info.states, info.stored);
+    match info.visited {
+        Some(visited) => println!("Visited states:  {}", visited),
+        None => println!("Visited states:  not tracked"),
+    }
+    println!("Episodes:        {}", info.episodes);
+    println!("Steps:           {}", info.steps);
+    println!("Best score:      {}", info.best_score);
+    println!("Epsilon:         {}", info.epsilon);
+    match info.trained {
+        Some(date) => println!("Trained:         {}", date),
+        None => println!("Trained:         unknown"),
+    }
+    if let Some(reason) = info.stop_reason {
+        println!("Stopped:         {}", reason.description());
+    }
+    println!("Alpha:           {}", info.params.alpha);
+    println!("Epsilon decay:   {}", info.params.epsilon);
+    println!("Gamma:           {}", info.params.gamma);
+    println!(
+        "Rewards:         {} per step, {} on collision",
+        info.params.survival_reward, info.params.collision_reward
+    );
+}
+
+fn run_diff(args: &[String]) -> Result<()> {
+    let (files, options) = split_options(args);
+    let [left, right] = files.as_slice() else {
+        return Err(GameError::InvalidConfig("diff needs two files".to_string()));
+    };
+
+    let mut limit = DEFAULT_DIFF_LIMIT;
+    for (option, value) in options {
+        match option.as_str() {
+            "--limit" => limit = parse_number(&option, &value)?,
+            _ => return Err(unknown_option(&option)),
+        }
+    }
+
+    let diffs = diff(&Agent::load(left)?, &Agent::load(right)?)?;
+    println!("{} states pick a different action", diffs.len());
+    for d in diffs.iter().take(limit) {
+        let visits = d
+            .visits
+            .map_or(String::new(), |v| format!("  ({} visits)", v));
+        println!(
+            "  {:>8}  {:<5} -> {:<5}{}",
+            d.state,
+            format!("{:?}", d.left),
+            format!("{:?}", d.right),
+            visits
+        );
+    }
+    if diffs.len() > limit {
+        println!("  ... {} more", diffs.len() - limit);
+    }
+    Ok(())
+}
+
+fn run_merge(args: &[String]) -> Result<()> {
+    let (files, options) = split_options(args);
+    let Some((output, inputs)) = files.split_first() else {
+        return Err(GameError::InvalidConfig(
+            "merge needs an output file".to_string(),
+        ));
+    };
+    if inputs.is_empty() {
+        return Err(GameError::InvalidConfig(
+            "merge needs at least one model".to_string(),
+        ));
+    }
+
+    let mut mode = MergeMode::default();
+    let mut weights = vec![1.0; inputs.len()];
+    for (option, value) in options {
+        match option.as_str() {
+            "--mode" => mode = value.parse()?,
+            "--weights" => {
+                weights = value
+                    .split(',')
+                    .map(|w| parse_number::<f64>(&option, w))
+                    .collect::<Result<_>>()?;
+                if weights.len() != inputs.len() || !valid_weights(weights.iter().copied()) {
+                    return Err(GameError::InvalidConfig(
+                        "--weights needs one non-negative weight per model, not all zero"
+                            .to_string(),
+                    ));
+                }
+            }
+            _ => return Err(unknown_option(&option)),
+        }
+    }
+
+    let agents = inputs
+        .iter()
+        .map(|file| Agent::load(file))
+        .collect::<Result<Vec<_>>>()?;
+    let models: Vec<(&Agent, f64)> = agents.iter().zip(weights).collect();
+    let merged = merge(&models, mode)?;
+    merged.save(output)?;
+
+    println!("Merged {} models ({}) into {}", agents.len(), mode, output);
+    Ok(())
+}
+
+fn run_migrate(args: &[String]) -> Result<()> {
+    let [file] = args else {
+        return Err(GameError::InvalidConfig("migrate needs a file".to_string()));
+    };
+
+    let version = migrate(file)?;
+    if version < MODEL_VERSION {
+        println!(
+            "Migrated {} from version {} to {}",
+            file, version, MODEL_VERSION
+        );
+    } else {
+        println!("{} is already version {}", file, MODEL_VERSION);
+    }
+    Ok(())
+}
+
+fn evaluate(args: &[String]) -> Result<()> {
+    let (files, options) = split_options(args);
+    let [file] = files.as_slice() else {
+        return Err(GameError::InvalidConfig(
+            "evaluate needs a file".to_string(),
+        ));
+    };
+
+    let mut courses = DEFAULT_SUITE_COURSES;
+    let mut seed = DEFAULT_SUITE_SEED;
+    let mut max_steps = DEFAULT_MAX_STEPS;
+    for (option, value) in options {
+        match option.as_str() {
+            "--courses" => courses = parse_number(&option, &value)?,
+            "--seed" => seed = parse_number(&option, &value)?,
+            "--max-steps" => max_steps = parse_number(&option, &value)?,
+            _ => return Err(unknown_option(&option)),
+        }
+    }
+    if courses == 0 {
+        return Err(GameError::InvalidConfig(
+            "--courses must be at least 1".to_string(),
+        ));
+    }
+
+    let policy = load_policy(file)?;
+    let evaluation = EvalSuite::new(seed, courses, max_steps).evaluate(policy.as_ref());
+
+    let (mean_low, mean_high) = evaluation.mean_interval();
+    let (crash_low, crash_high) = evaluation.crash_rate_interval();
+    println!(
+        "{} on {} courses (seed {}, up to {} steps)\n",
+        file, courses, seed, max_steps
+    );
+    println!(
+        "Mean:        {:.1}  (95% CI {:.1} to {:.1})",
+        evaluation.mean(),
+        mean_low,
+        mean_high
+    );
+    println!("Median:      {}", evaluation.median());
+    let percentiles: Vec<String> = [5.0, 25.0, 75.0, 95.0]
+        .iter()
+        .map(|&p| format!("p{} {}", p, evaluation.percentile(p)))
+        .collect();
+    println!("Percentiles: {}", percentiles.join("  "));
+    println!(
+        "Crash rate:  {:.1}%  (95% CI {:.1}% to {:.1}%)",
+        evaluation.crash_rate() * 100.0,
+        crash_low * 100.0,
+        crash_high * 100.0
+    );
+
+    println!("\nSurvival:");
+    for (distance, survived) in evaluation.survival_curve(SURVIVAL_POINTS) {
+        println!(
+            "  {:>7}  {:5.1}%  {}",
+            distance,
+            survived * 100.0,
+            "#".repeat((survived * 40.0).round() as usize)
+        );
+    }
+    Ok(())
+}
+
+/// Splits arguments into positional ones and `--option value` pairs
+fn split_options(args: &[String]) -> (Vec<String>, Vec<(String, String)>) {
+    let mut positional = Vec::new();
+    let mut options = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg.starts_with("--") {
+            options.push((arg.clone(), args.next().cloned().unwrap_or_default()));
+        } else {
+            positional.push(arg.clone());
+        }
+    }
+    (positional, options)
+}
+
+fn unknown_option(option: &str) -> GameError {
+    GameError::InvalidConfig(format!("unknown argument: {}", option))
+}
+
+fn parse_number<T: std::str::FromStr>(option: &str, value: &str) -> Result<T> {
+    value
+        .parse()
+        .map_err(|_| GameError::InvalidConfig(format!("invalid value for {}: {}", option, value)))
+}
@@ -1,43 +1,156 @@
-use pancurses::{endwin, initscr, noecho, Input, Window};
+use pancurses::{endwin, Input, Window};
+use rand::Rng;
 use std::env;
 use std::time::{Duration, Instant};
-use textdrive::game::{
-    Cell, Direction, Game, COLS_COUNT, FRAME_DELAY_US, PLAYER_ROW, ROWS_COUNT, SCROLL_DELAY_MS,
-};
-use textdrive::qlearning::{do_action, get_best_action, get_state, Agent};
+use textdrive::challenge::{days_in_month, ChallengeHistory, CourseCode, Date};
+use textdrive::game::{Direction, Game, FRAME_DELAY_US, ROWS_COUNT, SCROLL_DELAY_MS};
+use textdrive::policy::{load_policy, Policy};
+use textdrive::postmortem::Replay;
+use textdrive::qlearning::{do_action, Action, NUM_ACTIONS};
+use textdrive::race;
+use textdrive::render::{draw_game_field, draw_game_field_at, init_terminal, FIELD_WIDTH};
+use textdrive::rewind::{Rewind, DEFAULT_REWIND_CHARGES};
 
 const QTABLE_FILE: &str = "qtable.bin";
+const CHALLENGE_FILE: &str = "challenges.txt";
+const PANE_WIDTH: i32 = FIELD_WIDTH + 6;
 
+/// Every car on screen, plus what a single-player run keeps
+///
+/// Rewinds, replays and challenges apply to single-player runs only; with more
+/// than one car `race` keeps score instead.
 struct GameState {
-    game: Game,
-    ai_mode: bool,
-    agent: Option<Agent>,
+    players: Vec<Player>,
+    race: Option<Race>,
     last_scroll: Instant,
+    scroll_delay: Duration,
+    rewind: Rewind,
+    practice: bool,
+    replay: Replay,
+    postmortem: Option<PostMortemView>,
+    course: Option<CourseCode>,
+    challenge: Option<Date>,
+    history: ChallengeHistory,
+    history_month: Option<Date>,
+}
+
+/// Playback position of the post-mortem view
+struct PostMortemView {
+    cursor: usize,
+    no_return: Option<usize>,
+    playing: bool,
+    last_step: Instant,
+}
+
+impl PostMortemView {
+    fn new(replay: &Replay, moves_per_tick: usize) -> Self {
+        Self {
+            cursor: 0,
+            no_return: replay.point_of_no_return(moves_per_tick),
+            playing: false,
+            last_step: Instant::now(),
+        }
+    }
+
+    fn step_back(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    fn step_forward(&mut self, frame_count: usize) {
+        if self.cursor + 1 < frame_count {
+            self.cursor += 1;
+        }
+    }
+
+    fn update(&mut self, frame_count: usize) {
+        let now = Instant::now();
+        if self.playing
+            && now.duration_since(self.last_step) >= Duration::from_millis(SCROLL_DELAY_MS)
+        {
+            self.step_forward(frame_count);
+            self.playing = self.cursor + 1 < frame_count;
+            self.last_step = now;
+        }
+    }
 }
 
 impl GameState {
     fn new() -> Self {
         Self {
-            game: Game::new(),
-            ai_mode: false,
-            agent: None,
+            players: vec![Player::human("YOU", ALL_KEYS)],
+            race: None,
             last_scroll: Instant::now(),
+            scroll_delay: Duration::from_millis(SCROLL_DELAY_MS),
+            rewind: Rewind::default(),
+            practice: false,
+            replay: Replay::default(),
+            postmortem: None,
+            course: None,
+            challenge: None,
+            history: ChallengeHistory::load(CHALLENGE_FILE).unwrap_or_default(),
+            history_month: None,
         }
     }
 
-    fn with_ai(agent: Agent) -> Self {
+    fn with_course(code: CourseCode) -> Self {
+        let mut state = Self {
+            scroll_delay: Duration::from_millis(code.scroll_delay_ms),
+            course: Some(code),
+            ..Self::new()
+        };
+        state.player_mut().game = Game::with_seed(u64::from(code.seed));
+        state
+    }
+
+    fn daily() -> Self {
+        let today = Date::today();
+        let mut state = Self::with_course(CourseCode::daily(today));
+        // Only the first attempt of the day is scored
+        state.practice = state.history.has_played(today);
+        state.challenge = Some(today);
+        state
+    }
+
+    fn with_ai(agent: Box<dyn Policy>) -> Self {
         Self {
-            game: Game::new(),
-            ai_mode: true,
-            agent: Some(agent),
-            last_scroll: Instant::now(),
+            players: vec![Player::ai("AI", agent, STRENGTHS[2])],
+            ..Self::new()
         }
     }
 
+    /// A match between several cars, best of `rounds`
+    fn with_race(players: Vec<Player>, shared_course: bool, rounds: u32) -> Self {
+        let mut state = Self {
+            players,
+            race: Some(Race {
+                shared_course,
+                rounds,
+                round_over: false,
+            }),
+            ..Self::new()
+        };
+        state.new_round();
+        state
+    }
+
+    /// The car of a single-player run
+    fn player(&self) -> &Player {
+        &self.players[0]
+    }
+
+    fn player_mut(&mut self) -> &mut Player {
+        &mut self.players[0]
+    }
+
+    fn game(&self) -> &Game {
+        &self.player().game
+    }
+
     fn load_agent(&mut self) -> bool {
-        if self.agent.is_none() {
-            if let Ok(loaded_agent) = Agent::load(QTABLE_FILE) {
-                self.agent = Some(loaded_agent);
+        let player = self.player_mut();
+        if player.agent.is_none() {
+            if let Ok(loaded_agent) = load_policy(QTABLE_FILE) {
+                player.agent = Some(loaded_agent);
                 return true;
             }
         }
@@ -45,44 +158,395 @@ impl GameState {
     }
 
     fn toggle_ai_mode(&mut self) {
-        if self.agent.is_some() {
-            self.ai_mode = !self.ai_mode;
+        let player = &mut self.players[0];
+        if player.agent.is_some() {
+            player.ai_mode = !player.ai_mode;
+            // The AI can't play a scored challenge run
+            if self.challenge.is_some() {
+                self.practice = true;
+            }
+        }
+    }
+
+    fn restart(&mut self) {
+        if self.race.is_some() {
+            if self.match_winner().is_some() {
+                for player in &mut self.players {
+                    player.wins = 0;
+                }
+            }
+            self.new_round();
+            return;
+        }
+
+        let course = self.course.is_some();
+        let game = &mut self.player_mut().game;
+        if course {
+            game.restart();
+        } else {
+            game.init();
+        }
+        self.rewind.reset(DEFAULT_REWIND_CHARGES);
+        self.practice = self
+            .challenge
+            .is_some_and(|date| self.history.has_played(date));
+        self.replay.clear();
+        self.postmortem = None;
+        self.last_scroll = Instant::now();
+    }
+
+    fn begin_rewind(&mut self) {
+        // Rewinding after a crash is a practice tool and doesn't cost a charge
+        let free = self.game().game_over;
+        if self.rewind.begin(free) && free {
+            self.practice = true;
+        }
+    }
+
+    fn commit_rewind(&mut self) {
+        if let Some(game) = self.rewind.commit() {
+            self.replay.truncate_to(game.distance);
+            self.player_mut().game = game;
+            self.last_scroll = Instant::now();
+        }
+    }
+
+    fn finish_run(&mut self) {
+        let Some(date) = self.challenge else {
+            return;
+        };
+        let distance = self.game().distance;
+        if !self.practice && self.history.record(date, distance) {
+            // Losing the history file only costs the calendar, not the game
+            let _ = self.history.save(CHALLENGE_FILE);
+        }
+    }
+
+    fn toggle_history(&mut self) {
+        self.history_month = match self.history_month {
+            Some(_) => None,
+            None => Some(self.challenge.unwrap_or_else(Date::today).first_of_month()),
+        };
+    }
+
+    fn open_postmortem(&mut self) {
+        if self.game().game_over && !self.replay.is_empty() {
+            self.postmortem = Some(PostMortemView::new(&self.replay, self.moves_per_tick()));
+        }
+    }
+
+    /// Most cells the car can move between two scrolls
+    ///
+    /// The AI moves once per scroll; a player gets one key press per frame.
+    fn moves_per_tick(&self) -> usize {
+        if self.player().ai_mode {
+            1
+        } else {
+            (self.scroll_delay.as_micros() / FRAME_DELAY_US as u128).max(1) as usize
+        }
+    }
+
+    /// Puts every car at the start of a course
+    fn new_round(&mut self) {
+        let Some(ref mut race) = self.race else {
+            return;
+        };
+        let seed = rand::rng().random();
+        for player in &mut self.players {
+            player.game = if race.shared_course {
+                Game::with_seed(seed)
+            } else {
+                Game::new()
+            };
+        }
+        race.round_over = false;
+        self.last_scroll = Instant::now();
+    }
+
+    /// Ends the round, which the cars that got furthest win
+    fn end_round(&mut self) {
+        if let Some(ref mut race) = self.race {
+            race.round_over = true;
+        }
+        for i in race::round_winners(&self.distances()) {
+            self.players[i].wins += 1;
+        }
+    }
+
+    fn distances(&self) -> Vec<i32> {
+        self.players
+            .iter()
+            .map(|player| player.game.distance)
+            .collect()
+    }
+
+    fn match_winner(&self) -> Option<&Player> {
+        let needed = self.race.as_ref()?.wins_needed();
+        self.players.iter().find(|player| player.wins >= needed)
+    }
+
+    /// Whether the run, or the round of a match, is over
+    fn is_over(&self) -> bool {
+        match self.race {
+            Some(ref race) => race.round_over,
+            None => self.game().game_over,
         }
     }
 
     fn update(&mut self) {
-        if self.game.game_over {
+        if self.history_month.is_some() {
+            return;
+        }
+        if let Some(ref mut view) = self.postmortem {
+            view.update(self.replay.len());
+            return;
+        }
+
+        if self.is_over() || self.rewind.is_scrubbing() {
             return;
         }
 
         let now = Instant::now();
-        if now.duration_since(self.last_scroll) >= Duration::from_millis(SCROLL_DELAY_MS) {
-            if self.ai_mode {
-                if let Some(ref agent) = self.agent {
-                    let state = get_state(&self.game);
-                    let action = get_best_action(agent, state);
-                    do_action(&mut self.game, action);
-                }
+        if now.duration_since(self.last_scroll) < self.scroll_delay {
+            return;
+        }
+        self.last_scroll = now;
+
+        // All courses scroll in lockstep; a car that crashes drops out while
+        // the others race on
+        let solo = self.race.is_none();
+        for player in self.players.iter_mut().filter(|p| !p.game.game_over) {
+            let q_values = player.act();
+            player.game.scroll_course();
+            if solo {
+                self.replay.record(&player.game, q_values);
             }
 
-            self.game.scroll_course();
-            self.last_scroll = now;
+            if player.game.has_collision() {
+                player.game.game_over = true;
+            } else if solo {
+                self.rewind.record(&player.game);
+            }
+        }
 
-            if self.game.has_collision() {
-                self.game.game_over = true;
+        if solo {
+            if self.game().game_over {
+                self.finish_run();
             }
+        } else if self.players.iter().all(|player| player.game.game_over) {
+            self.end_round();
         }
     }
 }
 
+/// AI opponent strength, expressed as the chance of a random move
+#[derive(Clone, Copy)]
+struct Strength {
+    name: &'static str,
+    noise: f64,
+}
+
+const STRENGTHS: [Strength; 3] = [
+    Strength {
+        name: "easy",
+        noise: 0.3,
+    },
+    Strength {
+        name: "normal",
+        noise: 0.1,
+    },
+    Strength {
+        name: "hard",
+        noise: 0.0,
+    },
+];
+
+impl Strength {
+    fn parse(text: &str) -> Option<Self> {
+        STRENGTHS
+            .iter()
+            .copied()
+            .find(|strength| strength.name == text)
+            .or_else(|| {
+                let noise: f64 = text.parse().ok()?;
+                (0.0..=1.0).contains(&noise).then_some(Strength {
+                    name: "custom",
+                    noise,
+                })
+            })
+    }
+}
+
+/// Keys that steer one human player
+struct KeySet {
+    left: &'static [Input],
+    right: &'static [Input],
+}
+
+const WASD_KEYS: KeySet = KeySet {
+    left: &[Input::Character('a'), Input::Character('A')],
+    right: &[Input::Character('d'), Input::Character('D')],
+};
+
+const ARROW_KEYS: KeySet = KeySet {
+    left: &[Input::KeyLeft],
+    right: &[Input::KeyRight],
+};
+
+const NO_KEYS: KeySet = KeySet {
+    left: &[],
+    right: &[],
+};
+
+const ALL_KEYS: KeySet = KeySet {
+    left: &[Input::KeyLeft, Input::Character('a'), Input::Character('A')],
+    right: &[
+        Input::KeyRight,
+        Input::Character('d'),
+        Input::Character('D'),
+    ],
+};
+
+impl KeySet {
+    fn direction(&self, input: Input) -> Option<Direction> {
+        if self.left.contains(&input) {
+            Some(Direction::Left)
+        } else if self.right.contains(&input) {
+            Some(Direction::Right)
+        } else {
+            None
+        }
+    }
+}
+
+/// One car and whoever steers it
+struct Player {
+    name: String,
+    game: Game,
+    keys: KeySet,
+    agent: Option<Box<dyn Policy>>,
+    strength: Strength,
+    /// Whether the agent steers instead of the keys
+    ai_mode: bool,
+    wins: u32,
+}
+
+impl Player {
+    fn human(name: &str, keys: KeySet) -> Self {
+        Self {
+            name: name.to_string(),
+            game: Game::new(),
+            keys,
+            agent: None,
+            strength: STRENGTHS[2],
+            ai_mode: false,
+            wins: 0,
+        }
+    }
+
+    fn ai(name: &str, agent: Box<dyn Policy>, strength: Strength) -> Self {
+        Self {
+            agent: Some(agent),
+            strength,
+            ai_mode: true,
+            ..Self::human(name, NO_KEYS)
+        }
+    }
+
+    fn handle_input(&mut self, input: Input) {
+        if self.game.game_over || self.ai_mode {
+            return;
+        }
+        if let Some(direction) = self.keys.direction(input) {
+            self.game.move_player(direction);
+        }
+    }
+
+    /// Lets the agent move, returning the Q-values it chose from
+    fn act(&mut self) -> Option<[f64; NUM_ACTIONS]> {
+        let agent = self.agent.as_ref().filter(|_| self.ai_mode)?;
+        let state = agent.state(&self.game);
+        let mut rng = rand::rng();
+        let action = if rng.random::<f64>() < self.strength.noise {
+            Action::all()[rng.random_range(0..NUM_ACTIONS)]
+        } else {
+            agent.best_action(state)
+        };
+        do_action(&mut self.game, action);
+        Some(agent.q_values(state))
+    }
+}
+
+/// Rounds of a multi-player match
+struct Race {
+    shared_course: bool,
+    rounds: u32,
+    round_over: bool,
+}
+
+impl Race {
+    /// Wins needed to take a best-of-N match
+    fn wins_needed(&self) -> u32 {
+        self.rounds / 2 + 1
+    }
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
-    let mut state = if args.len() > 1 && args[1] == "ai" {
-        Agent::load(QTABLE_FILE)
+    let mut state = match args.get(1).map(String::as_str) {
+        Some("ai") => load_policy(QTABLE_FILE)
             .map(GameState::with_ai)
-            .unwrap_or_else(|_| GameState::new())
-    } else {
-        GameState::new()
+            .unwrap_or_else(|_| GameState::new()),
+        Some("daily") => GameState::daily(),
+        Some("code") => match args.get(2).map(|code| code.parse::<CourseCode>()) {
+            Some(Ok(code)) => GameState::with_course(code),
+            Some(Err(err)) => {
+                eprintln!("{}", err);
+                return;
+            }
+            None => {
+                eprintln!("Usage: textdrive code <COURSE-CODE>");
+                return;
+            }
+        },
+        Some("history") => {
+            let mut state = GameState::new();
+            state.toggle_history();
+            state
+        }
+        Some("versus") => {
+            let strength = args
+                .get(2)
+                .map_or(Some(STRENGTHS[1]), |s| Strength::parse(s));
+            let Some(strength) = strength else {
+                eprintln!("Usage: textdrive versus [easy|normal|hard|<noise 0.0-1.0>]");
+                return;
+            };
+            let agent = match load_policy(QTABLE_FILE) {
+                Ok(agent) => agent,
+                Err(err) => {
+                    eprintln!("Versus mode needs a trained {}: {}", QTABLE_FILE, err);
+                    return;
+                }
+            };
+            let players = vec![
+                Player::human("YOU", ALL_KEYS),
+                Player::ai(&format!("AI ({})", strength.name), agent, strength),
+            ];
+            GameState::with_race(players, true, 1)
+        }
+        Some("duel") => {
+            let Some((shared_course, rounds)) = parse_duel_args(&args[2..]) else {
+                eprintln!("Usage: textdrive duel [--rounds N] [--independent]");
+                return;
+            };
+            let players = vec![
+                Player::human("P1 (A/D)", WASD_KEYS),
+                Player::human("P2 (<-/->)", ARROW_KEYS),
+            ];
+            GameState::with_race(players, shared_course, rounds)
+        }
+        _ => GameState::new(),
     };
 
     let window = init_terminal();
@@ -92,16 +556,6 @@ fn main() {
     endwin();
 }
 
-fn init_terminal() -> Window {
-    let window = initscr();
-    pancurses::cbreak();
-    noecho();
-    window.keypad(true);
-    window.nodelay(true);
-    pancurses::curs_set(0);
-    window
-}
-
 fn game_loop(window: &Window, state: &mut GameState) {
     loop {
         if let Some(input) = window.getch() {
@@ -112,21 +566,91 @@ fn game_loop(window: &Window, state: &mut GameState) {
 
         state.update();
 
-        if state.game.game_over {
-            draw_game_over(window, &state.game);
+        if state.race.is_some() {
+            draw_match(window, state);
+        } else if let Some(month) = state.history_month {
+            draw_history(window, &state.history, month);
+        } else if let Some(ref view) = state.postmortem {
+            draw_postmortem(window, &state.replay, view);
+        } else if let Some(preview) = state.rewind.preview() {
+            draw_rewind(window, preview, &state.rewind);
+        } else if state.game().game_over {
+            draw_game_over(window, state);
         } else {
-            draw(window, &state.game, state.ai_mode);
+            draw(window, state);
         }
 
         std::thread::sleep(Duration::from_micros(FRAME_DELAY_US));
     }
 }
 
+fn parse_duel_args(args: &[String]) -> Option<(bool, u32)> {
+    let mut shared_course = true;
+    let mut rounds = 3;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--independent" => shared_course = false,
+            "--rounds" => rounds = iter.next()?.parse().ok().filter(|&n| n > 0)?,
+            _ => return None,
+        }
+    }
+    Some((shared_course, rounds))
+}
+
+fn handle_race_input(state: &mut GameState, input: Input) -> bool {
+    match input {
+        Input::Character('q') | Input::Character('Q') => return false,
+        Input::Character('r') | Input::Character('R') => state.restart(),
+        Input::Character(' ') if state.is_over() => {
+            if state.match_winner().is_none() {
+                state.new_round();
+            }
+        }
+        // Once a car is out the rest can be waved through
+        Input::Character(' ') if state.players.iter().any(|p| p.game.game_over) => {
+            state.end_round();
+        }
+        _ => {
+            for player in &mut state.players {
+                player.handle_input(input);
+            }
+        }
+    }
+
+    true
+}
+
 fn handle_input(state: &mut GameState, input: Input) -> bool {
+    if state.race.is_some() {
+        return handle_race_input(state, input);
+    }
+    if state.history_month.is_some() {
+        return handle_history_input(state, input);
+    }
+    if state.postmortem.is_some() {
+        return handle_postmortem_input(state, input);
+    }
+    if state.rewind.is_scrubbing() {
+        return handle_rewind_input(state, input);
+    }
+
     match input {
         Input::Character('q') | Input::Character('Q') => return false,
         Input::Character('r') | Input::Character('R') => {
-            state.game.init();
+            state.restart();
+            return true;
+        }
+        Input::Character('z') | Input::Character('Z') => {
+            state.begin_rewind();
+            return true;
+        }
+        Input::Character('p') | Input::Character('P') => {
+            state.open_postmortem();
+            return true;
+        }
+        Input::Character('h') | Input::Character('H') if state.game().game_over => {
+            state.toggle_history();
             return true;
         }
         Input::Character('m') | Input::Character('M') => {
@@ -134,19 +658,57 @@ fn handle_input(state: &mut GameState, input: Input) -> bool {
             state.toggle_ai_mode();
             return true;
         }
+        _ => state.player_mut().handle_input(input),
+    }
+
+    true
+}
+
+fn handle_rewind_input(state: &mut GameState, input: Input) -> bool {
+    match input {
+        Input::Character('q') | Input::Character('Q') => return false,
+        Input::KeyLeft
+        | Input::Character('a')
+        | Input::Character('A')
+        | Input::Character('z')
+        | Input::Character('Z') => {
+            state.rewind.step_back();
+        }
+        Input::KeyRight | Input::Character('d') | Input::Character('D') => {
+            state.rewind.step_forward();
+        }
+        Input::Character(' ') | Input::Character('\n') | Input::KeyEnter => {
+            state.commit_rewind();
+        }
+        Input::Character('x') | Input::Character('X') | Input::Character('\u{1b}') => {
+            state.rewind.cancel();
+        }
         _ => {}
     }
 
-    if state.game.game_over || state.ai_mode {
+    true
+}
+
+fn handle_history_input(state: &mut GameState, input: Input) -> bool {
+    let Some(month) = state.history_month else {
         return true;
-    }
+    };
 
     match input {
+        Input::Character('q') | Input::Character('Q') => return false,
         Input::KeyLeft | Input::Character('a') | Input::Character('A') => {
-            state.game.move_player(Direction::Left);
+            state.history_month = Some(Date::from_days(month.to_days() - 1).first_of_month());
         }
         Input::KeyRight | Input::Character('d') | Input::Character('D') => {
-            state.game.move_player(Direction::Right);
+            let days = days_in_month(month.year, month.month);
+            state.history_month = Some(Date::from_days(month.to_days() + i64::from(days)));
+        }
+        Input::Character('h')
+        | Input::Character('H')
+        | Input::Character('x')
+        | Input::Character('X')
+        | Input::Character('\u{1b}') => {
+            state.toggle_history();
         }
         _ => {}
     }
@@ -154,61 +716,306 @@ fn handle_input(state: &mut GameState, input: Input) -> bool {
     true
 }
 
-fn draw(window: &Window, game: &Game, ai_mode: bool) {
-    window.clear();
+fn handle_postmortem_input(state: &mut GameState, input: Input) -> bool {
+    let frame_count = state.replay.len();
+    let Some(ref mut view) = state.postmortem else {
+        return true;
+    };
 
-    draw_header(window, game, ai_mode);
-    draw_game_field(window, game);
-    draw_controls(window, ai_mode);
+    match input {
+        Input::Character('q') | Input::Character('Q') => return false,
+        Input::KeyLeft | Input::Character('a') | Input::Character('A') => {
+            view.playing = false;
+            view.step_back();
+        }
+        Input::KeyRight | Input::Character('d') | Input::Character('D') => {
+            view.playing = false;
+            view.step_forward(frame_count);
+        }
+        Input::Character(' ') => {
+            if view.cursor + 1 == frame_count {
+                view.cursor = 0;
+            }
+            view.playing = !view.playing;
+            view.last_step = Instant::now();
+        }
+        Input::Character('p')
+        | Input::Character('P')
+        | Input::Character('x')
+        | Input::Character('X')
+        | Input::Character('\u{1b}') => {
+            state.postmortem = None;
+        }
+        _ => {}
+    }
 
-    window.refresh();
+    true
 }
 
-fn draw_header(window: &Window, game: &Game, ai_mode: bool) {
-    let mode_text = if ai_mode { "[AI MODE]" } else { "[MANUAL]" };
-    window.mvprintw(0, 0, format!("Distance: {}  {}", game.distance, mode_text));
-}
+fn draw(window: &Window, state: &GameState) {
+    window.clear();
 
-fn draw_game_field(window: &Window, game: &Game) {
-    const DISPLAY_OFFSET: i32 = 2;
-    const CELL_WIDTH: i32 = 2;
+    draw_header(window, state);
+    draw_game_field(window, state.game());
+    draw_controls(window, state.player().ai_mode);
 
-    for y in 0..ROWS_COUNT {
-        for x in 0..COLS_COUNT {
-            let ch = get_cell_char(game, x, y);
-            let y_pos = y as i32 + DISPLAY_OFFSET;
-            let x_pos = x as i32 * CELL_WIDTH;
-            window.mvaddstr(y_pos, x_pos, ch);
-        }
-    }
+    window.refresh();
 }
 
-fn get_cell_char(game: &Game, x: usize, y: usize) -> &'static str {
-    if y == PLAYER_ROW && x == game.player_x {
-        "車"
-    } else if game.get_cell(x, y) == Cell::Wall {
-        "■"
+fn draw_header(window: &Window, state: &GameState) {
+    let mode_text = if state.player().ai_mode {
+        "[AI MODE]"
     } else {
-        "  "
+        "[MANUAL]"
+    };
+    let practice_text = if state.practice { " [PRACTICE]" } else { "" };
+    window.mvprintw(
+        0,
+        0,
+        format!(
+            "Distance: {}  {}{}  Rewinds: {}",
+            state.game().distance,
+            mode_text,
+            practice_text,
+            state.rewind.charges()
+        ),
+    );
+
+    if let Some(code) = state.course {
+        let course_text = match state.challenge {
+            Some(date) => format!("Daily {}  Code: {}", date, code),
+            None => format!("Code: {}", code),
+        };
+        window.mvprintw(1, 0, course_text);
     }
 }
 
 fn draw_controls(window: &Window, ai_mode: bool) {
     let controls = if ai_mode {
-        "[M] Manual  [R] Restart  [Q] Quit"
+        "[M] Manual  [Z] Rewind  [R] Restart  [Q] Quit"
     } else {
-        "[<-][->] Move  [M] AI  [R] Restart  [Q] Quit"
+        "[<-][->] Move  [M] AI  [Z] Rewind  [R] Restart  [Q] Quit"
     };
     window.mvprintw((ROWS_COUNT + 3) as i32, 0, controls);
 }
 
-fn draw_game_over(window: &Window, game: &Game) {
+fn draw_game_over(window: &Window, state: &GameState) {
     window.clear();
 
     let center_y = (ROWS_COUNT / 2) as i32;
     window.mvprintw(center_y, 2, "GAME OVER");
-    window.mvprintw(center_y + 2, 0, format!("Distance: {}", game.distance));
-    window.mvprintw(center_y + 4, 0, "[R] Restart  [Q] Quit");
+    window.mvprintw(
+        center_y + 2,
+        0,
+        format!("Distance: {}", state.game().distance),
+    );
+    if let Some(score) = state.challenge.and_then(|date| state.history.get(date)) {
+        window.mvprintw(center_y + 3, 0, format!("Today's score: {}", score));
+    }
+    window.mvprintw(center_y + 4, 0, "[P] Post-mortem  [Z] Rewind  [H] History");
+    window.mvprintw(center_y + 5, 0, "[R] Restart  [Q] Quit");
+
+    window.refresh();
+}
+
+fn draw_history(window: &Window, history: &ChallengeHistory, month: Date) {
+    const MONTH_NAMES: [&str; 12] = [
+        "January",
+        "February",
+        "March",
+        "April",
+        "May",
+        "June",
+        "July",
+        "August",
+        "September",
+        "October",
+        "November",
+        "December",
+    ];
+    const CELL_WIDTH: i32 = 6;
+
+    window.clear();
+
+    let month_name = MONTH_NAMES[month.month as usize - 1];
+    window.mvprintw(
+        0,
+        0,
+        format!("Daily Challenge - {} {}", month_name, month.year),
+    );
+    for (i, name) in ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"]
+        .iter()
+        .enumerate()
+    {
+        window.mvprintw(2, i as i32 * CELL_WIDTH, *name);
+    }
+
+    // Each week takes two lines: day numbers, then the scores
+    let today = Date::today();
+    let offset = month.weekday();
+    for day in 1..=days_in_month(month.year, month.month) {
+        let slot = offset + day - 1;
+        let x = (slot % 7) as i32 * CELL_WIDTH;
+        let y = 3 + (slot / 7) as i32 * 2;
+        let date = Date { day, ..month };
+        let marker = if date == today { "*" } else { "" };
+        window.mvprintw(y, x, format!("{}{}", day, marker));
+        if let Some(score) = history.get(date) {
+            window.mvprintw(y + 1, x, score.to_string());
+        }
+    }
+
+    let footer_y = 3 + 6 * 2;
+    if let Some((date, score)) = history.best() {
+        window.mvprintw(footer_y, 0, format!("Best: {} on {}", score, date));
+    }
+    window.mvprintw(footer_y + 1, 0, "[<-][->] Month  [H] Close  [Q] Quit");
+
+    window.refresh();
+}
+
+fn draw_rewind(window: &Window, game: &Game, rewind: &Rewind) {
+    window.clear();
+
+    let seconds = (rewind.offset() as u64 * SCROLL_DELAY_MS) as f64 / 1000.0;
+    window.mvprintw(
+        0,
+        0,
+        format!("Distance: {}  [REWIND -{:.1}s]", game.distance, seconds),
+    );
+    draw_game_field(window, game);
+    window.mvprintw(
+        (ROWS_COUNT + 3) as i32,
+        0,
+        "[<-][->] Scrub  [Space] Resume  [X] Cancel  [Q] Quit",
+    );
+
+    window.refresh();
+}
+
+fn draw_postmortem(window: &Window, replay: &Replay, view: &PostMortemView) {
+    let Some(frame) = replay.frame(view.cursor) else {
+        return;
+    };
+
+    window.clear();
+
+    window.mvprintw(
+        0,
+        0,
+        format!(
+            "POST-MORTEM  Tick {}/{}  Distance: {}",
+            view.cursor + 1,
+            replay.len(),
+            frame.game.distance
+        ),
+    );
+    draw_game_field(window, &frame.game);
+
+    let info_y = (ROWS_COUNT + 3) as i32;
+    window.mvprintw(info_y, 0, postmortem_timeline(replay.len(), view));
+    let verdict = match view.no_return {
+        Some(tick) if tick == view.cursor => "<< Crash became unavoidable here",
+        Some(tick) if tick < view.cursor => "Crash is unavoidable",
+        Some(_) => "Crash still avoidable",
+        None => "",
+    };
+    window.mvprintw(info_y + 1, 0, verdict);
+    if let Some(q_values) = frame.q_values {
+        window.mvprintw(info_y + 2, 0, format_q_values(&q_values));
+    }
+    window.mvprintw(
+        info_y + 3,
+        0,
+        "[<-][->] Step  [Space] Play  [P] Close  [Q] Quit",
+    );
+
+    window.refresh();
+}
+
+/// Renders one character per tick: `>` the current tick, `!` the point of no
+/// return, `x` doomed ticks and `.` ticks where the crash was still avoidable
+fn postmortem_timeline(frame_count: usize, view: &PostMortemView) -> String {
+    (0..frame_count)
+        .map(|tick| {
+            if tick == view.cursor {
+                '>'
+            } else {
+                match view.no_return {
+                    Some(no_return) if tick == no_return => '!',
+                    Some(no_return) if tick > no_return => 'x',
+                    _ => '.',
+                }
+            }
+        })
+        .collect()
+}
+
+fn format_q_values(q_values: &[f64]) -> String {
+    let labels = Action::all().map(|action| match action {
+        Action::Left => "<-",
+        Action::Stay => "==",
+        Action::Right => "->",
+    });
+    let parts: Vec<String> = labels
+        .iter()
+        .zip(q_values)
+        .map(|(label, q)| format!("{} {:8.2}", label, q))
+        .collect();
+    format!("Q: {}", parts.join("  "))
+}
+
+fn draw_match(window: &Window, state: &GameState) {
+    window.clear();
+
+    for (i, player) in state.players.iter().enumerate() {
+        draw_player_pane(window, player, i as i32 * PANE_WIDTH);
+    }
+
+    let info_y = (ROWS_COUNT + 3) as i32;
+    window.mvprintw(info_y, 0, match_leader_text(state));
+    let wins: Vec<String> = state
+        .players
+        .iter()
+        .map(|player| format!("{} {}", player.name, player.wins))
+        .collect();
+    let rounds = state.race.as_ref().map_or(1, |race| race.rounds);
+    window.mvprintw(
+        info_y + 1,
+        0,
+        format!("Wins  {}  (best of {})", wins.join("  "), rounds),
+    );
+    let controls = if let Some(winner) = state.match_winner() {
+        format!("{} wins the match!  [R] New match  [Q] Quit", winner.name)
+    } else if state.is_over() {
+        "[Space] Next round  [R] Restart  [Q] Quit".to_string()
+    } else if state.players.iter().any(|player| player.game.game_over) {
+        "[Space] End round  [R] Restart  [Q] Quit".to_string()
+    } else {
+        "[R] Restart  [Q] Quit".to_string()
+    };
+    window.mvprintw(info_y + 2, 0, controls);
 
     window.refresh();
 }
+
+fn draw_player_pane(window: &Window, player: &Player, x_offset: i32) {
+    let status = if player.game.game_over {
+        " CRASHED"
+    } else {
+        ""
+    };
+    window.mvprintw(
+        0,
+        x_offset,
+        format!("{}: {}{}", player.name, player.game.distance, status),
+    );
+    draw_game_field_at(window, &player.game, x_offset);
+}
+
+fn match_leader_text(state: &GameState) -> String {
+    match race::leader(&state.distances()) {
+        Some((leader, lead)) => format!("{} leads by {}", state.players[leader].name, lead),
+        None => "Dead heat".to_string(),
+    }
+}
@@ -1,61 +1,629 @@
-use textdrive::game::Game;
-use textdrive::qlearning::{choose_action, do_action, get_reward, get_state, update_q, Agent};
+use pancurses::{endwin, Input, Window};
+use std::env;
+use std::fs::File;
+use std::io::BufWriter;
+use std::process;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use textdrive::config::TrainConfig;
+use textdrive::dashboard::Dashboard;
+use textdrive::encoder::StateEncoder;
+use textdrive::error::{GameError, Result};
+use textdrive::evaluation::{EvalSuite, Evaluation};
+use textdrive::export::QuantizedPolicy;
+use textdrive::metrics::{EpisodeMetrics, EvalMetrics, MetricsLog};
+use textdrive::parallel;
+use textdrive::qlearning::Agent;
+use textdrive::render::init_terminal;
+use textdrive::rng::{splitmix64, SimRng};
+use textdrive::stopping::StopReason;
+use textdrive::storage::StorageKind;
+use textdrive::sweep::{rank, SweepConfig, Trial};
+use textdrive::tensorboard::EventWriter;
+use textdrive::traces::CreditAssignment;
+use textdrive::training::train_episode;
 
-const NUM_EPISODES: usize = 50000;
-const MAX_STEPS: usize = 10000;
-const REPORT_INTERVAL: usize = 500;
-const RECENT_WINDOW_SIZE: usize = 500;
+const USAGE: &str = "Usage: train [--config FILE] [--SETTING VALUE]...
+       train sweep [--config FILE] [--SETTING VALUE]...
+
+Settings, also the keys of a TOML config file (defaults in brackets):
+  --episodes N                 episodes to train [50000]
+  --max-steps N                steps before an episode ends uncrashed [10000]
+  --report-interval N          episodes between progress reports [500]
+  --recent-window N            episodes the reported average covers [500]
+  --output FILE                where the model is saved [qtable.bin]
+  --checkpoint FILE            where checkpoints are saved [checkpoint.bin]
+  --checkpoint-every N         episodes between checkpoints [1000]
+  --eval-every N               episodes between greedy evaluations, 0 for none [5000]
+  --eval-courses N             courses in the evaluation suite [100]
+  --eval-seed N                seed of the evaluation suite
+  --best FILE                  where the best-evaluated agent is saved [best.bin]
+  --metrics FILE               write per-episode metrics to a .csv or .jsonl file
+  --tensorboard DIR            also write them as TensorBoard scalars to DIR
+  --tui                        show a live dashboard instead of progress lines
+  --workers N                  threads training in parallel [1]
+  --sync-every N               episodes each worker runs between merges [100]
+  --target-score S             stop once an evaluation's mean reaches S
+  --patience N                 stop after N evaluations without a new best
+  --min-q-change D             stop once Q-values change less than D per episode
+  --seed N | --resume FILE     start from a seed or a checkpoint
+  --learner q-learning|sarsa|expected-sarsa|double-q
+  --n-step N | --lambda L [--trace watkins|peng]
+  --encoder window[:AxR]|gap[:AxO]|walls[:AxD]
+  --storage dense|sparse
+  --alpha SCHEDULE  --epsilon SCHEDULE  --gamma G
+  --collision-reward R  --survival-reward R
+  --export FILE [--precision f16|i8|argmax] [--compress]
+
+Command-line settings override the config file.
+
+Sweep settings:
+  --alpha, --epsilon           schedules, comma-separated, or a range LOW..HIGH
+  --gamma, --collision-reward, --survival-reward
+                               numbers, comma-separated, or a range LOW..HIGH
+  --encoder                    encoders, comma-separated
+  --search grid|random         try every combination or random ones [grid]
+  --grid-points N              values a grid search tries across a range [3]
+  --trials N                   configurations a random search tries [20]
+  --seeds N                    runs per configuration [3]
+  --jobs N                     runs trained at once [one per core]
+
+A sweep ranks configurations by their evaluation and saves the best run's
+model to --output.";
 
 fn main() {
-    let mut agent = Agent::new();
-    let mut stats = TrainingStats::new(RECENT_WINDOW_SIZE);
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", USAGE);
+        return;
+    }
+    if args.first().map(String::as_str) == Some("sweep") {
+        return run_sweep(args[1..].to_vec());
+    }
+    let config = match parse_args(args) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{}", err);
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+    let learner = config.learner;
+    let mut credit = config.credit();
+
+    let (mut agent, mut courses, origin) = match config.resume {
+        None => {
+            let seed = config.seed.unwrap_or_else(rand::random);
+            // Exploration and courses draw from separate streams of the one seed
+            let mut seeds = seed;
+            let mut agent = Agent::with_storage(
+                config.encoder.clone().unwrap_or_default(),
+                config.params.unwrap_or_default(),
+                config.storage.unwrap_or_default(),
+            );
+            agent.seed(splitmix64(&mut seeds));
+            let courses = SimRng::new(splitmix64(&mut seeds));
+            (agent, courses, format!("Seed: {}", seed))
+        }
+        Some(ref path) => match Agent::load_checkpoint(path) {
+            Ok((agent, courses)) => {
+                let origin = format!("Resuming {} after {} episodes", path, agent.episodes);
+                (agent, courses, origin)
+            }
+            Err(err) => {
+                eprintln!("Failed to load {}: {}", path, err);
+                process::exit(1);
+            }
+        },
+    };
+    learner.prepare(&mut agent);
+    agent.track_visits();
+    let mut stats = TrainingStats::new(config.recent_window);
+    let mut sinks = match MetricsSinks::open(&config) {
+        Ok(sinks) => sinks,
+        Err(err) => {
+            eprintln!("Failed to open metrics output: {}", err);
+            process::exit(1);
+        }
+    };
+
+    let suite = EvalSuite::new(config.eval_seed, config.eval_courses, config.max_steps);
+    // A resumed run has to beat the best agent saved before it stopped
+    let mut best_mean = match config.resume {
+        Some(_) if config.eval_every > 0 => Agent::load(&config.best)
+            .map(|best| suite.evaluate(&best).mean())
+            .unwrap_or(f64::NEG_INFINITY),
+        _ => f64::NEG_INFINITY,
+    };
+
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let flag = Arc::clone(&interrupted);
+    if let Err(err) = ctrlc::set_handler(move || flag.store(true, Ordering::SeqCst)) {
+        eprintln!("Ctrl-C won't save a checkpoint: {}", err);
+    }
+
+    let mut output = if config.tui {
+        Output::dashboard(Dashboard::new(
+            config.episodes,
+            config.eval_seed,
+            config.max_steps,
+        ))
+    } else {
+        Output::Console
+    };
+    output.line(format!(
+        "=== Q-Learning Start ({}, {}, {} with {} {} states) ===",
+        learner,
+        describe_credit(&credit),
+        agent.encoder,
+        agent.q.len(),
+        agent.q.kind()
+    ));
+    output.line(origin);
+    if config.workers > 1 {
+        output.line(format!(
+            "{} workers, merging every {} episodes each",
+            config.workers, config.sync_every
+        ));
+    }
+    if !config.tui {
+        println!();
+    }
+
+    let mut stopping = config.early_stopping();
+    // Table at the last report, to measure how much Q-values still change
+    let mut snapshot = config.min_q_change.map(|_| agent.q.clone());
+    let mut stop = None;
+
+    let mut episode = agent.episodes as usize;
+    'training: while episode < config.episodes {
+        // With several workers, a round of episodes runs before any is reported
+        let batch = if config.workers > 1 {
+            let episodes = (config.workers * config.sync_every).min(config.episodes - episode);
+            let train = |worker: &mut Agent, worker_courses: &mut SimRng, count: usize| {
+                let mut credit = credit.clone();
+                (0..count)
+                    .map(|_| {
+                        train_episode(
+                            worker,
+                            learner,
+                            &mut credit,
+                            worker_courses,
+                            config.max_steps,
+                        )
+                    })
+                    .collect()
+            };
+            parallel::run_round(&mut agent, &mut courses, config.workers, episodes, train)
+                .expect("Failed to merge workers")
+        } else {
+            vec![train_episode(
+                &mut agent,
+                learner,
+                &mut credit,
+                &mut courses,
+                config.max_steps,
+            )]
+        };
+
+        for mut metrics in batch {
+            episode += 1;
+            metrics.episode = episode as u64;
+            metrics.states_visited = agent.visits.as_ref().map_or(0, |visits| visits.len());
+            stats.update(metrics.distance, &agent);
 
-    println!("=== Q-Learning Start ===\n");
+            if episode.is_multiple_of(config.report_interval) {
+                output.line(stats.progress_line(episode, &agent));
+                if let Some(ref mut previous) = snapshot {
+                    let change =
+                        agent.q.max_abs_difference(previous) / config.report_interval as f64;
+                    *previous = agent.q.clone();
+                    stop = stop.or(stopping.q_changed(change));
+                }
+            }
 
-    for episode in 1..=NUM_EPISODES {
-        let final_distance = run_episode(&mut agent);
+            if config.eval_every > 0 && episode.is_multiple_of(config.eval_every) {
+                let evaluation = suite.evaluate(&agent);
+                output.line(format_evaluation(&evaluation));
+                if evaluation.mean() > best_mean {
+                    best_mean = evaluation.mean();
+                    // A model rather than a checkpoint, so Double Q agents
+                    // save their averaged table and any policy loader opens it
+                    agent.save(&config.best).expect("Failed to save best agent");
+                    output.line(format!(
+                        "                New best, saved to {}",
+                        config.best
+                    ));
+                }
+                stop = stop.or(stopping.evaluated(evaluation.mean()));
+                metrics.evaluation = Some(EvalMetrics::from(&evaluation));
+            }
 
-        stats.update(final_distance, &agent);
-        agent.decay_epsilon();
+            sinks.record(&metrics);
+            if output.episode(&metrics, &agent) {
+                interrupted.store(true, Ordering::SeqCst);
+            }
+            if episode.is_multiple_of(config.report_interval) || stop.is_some() {
+                sinks.flush();
+            }
 
-        if episode % REPORT_INTERVAL == 0 {
-            stats.print_progress_with_agent(episode, &agent);
+            if interrupted.load(Ordering::SeqCst) {
+                sinks.flush();
+                output.close();
+                save_checkpoint(&agent, &courses, &config);
+                println!(
+                    "\nInterrupted after {} episodes; continue with --resume {}",
+                    agent.episodes, config.checkpoint
+                );
+                process::exit(130);
+            }
+            if episode.is_multiple_of(config.checkpoint_every) {
+                save_checkpoint(&agent, &courses, &config);
+            }
+            if stop.is_some() {
+                break 'training;
+            }
         }
     }
 
+    sinks.flush();
+    output.close();
+    let reason = stop.unwrap_or(StopReason::EpisodeLimit);
+    agent.stop_reason = Some(reason);
+
     println!("\n=== Training Complete ===");
+    println!(
+        "Stopped after {} episodes: {}",
+        agent.episodes,
+        reason.description()
+    );
     println!("Best Score: {}", agent.best_score);
+    if best_mean.is_finite() {
+        println!(
+            "Best Evaluation: {:.1} mean over {} courses ({})",
+            best_mean, config.eval_courses, config.best
+        );
+    }
+    if agent.q.kind() == StorageKind::Sparse {
+        println!("States Stored: {} / {}", agent.q.stored(), agent.q.len());
+    }
+
+    agent.save(&config.output).expect("Failed to save data");
+    println!("Data saved to {}", config.output);
+
+    if let Some(ref path) = config.export {
+        let policy = QuantizedPolicy::quantize(&agent, config.precision);
+        policy
+            .save(path, config.compress)
+            .expect("Failed to export policy");
+        let report = policy.compare(&agent);
+        println!(
+            "Exported {} policy to {}: {} of {} states ({:.2}%) changed their greedy action",
+            config.precision,
+            path,
+            report.changed_actions,
+            report.states,
+            report.changed_fraction() * 100.0
+        );
+    }
+}
+
+/// Where progress goes: the console, or a dashboard with `--tui`
+enum Output {
+    Console,
+    Dashboard(Window, Box<Dashboard>),
+}
+
+impl Output {
+    fn dashboard(dashboard: Dashboard) -> Self {
+        Output::Dashboard(init_terminal(), Box::new(dashboard))
+    }
+
+    /// Prints a progress line, or adds it to the dashboard's log
+    fn line(&mut self, text: String) {
+        match self {
+            Output::Console => println!("{}", text),
+            Output::Dashboard(_, dashboard) => dashboard.message(text),
+        }
+    }
+
+    /// Shows a finished episode; returns whether the user asked to stop
+    fn episode(&mut self, metrics: &EpisodeMetrics, agent: &Agent) -> bool {
+        let Output::Dashboard(window, dashboard) = self else {
+            return false;
+        };
+        dashboard.record(metrics);
+        if dashboard.due() {
+            dashboard.draw(window, agent);
+        }
+        matches!(window.getch(), Some(Input::Character('q' | 'Q')))
+    }
+
+    /// Gives the terminal back before the final report
+    fn close(&mut self) {
+        if let Output::Dashboard(..) = self {
+            endwin();
+            *self = Output::Console;
+        }
+    }
+}
+
+/// Where per-episode metrics go besides the console
+struct MetricsSinks {
+    log: Option<MetricsLog<BufWriter<File>>>,
+    events: Option<EventWriter>,
+}
 
-    agent.save("qtable.bin").expect("Failed to save data");
-    println!("Data saved successfully");
+impl MetricsSinks {
+    fn open(config: &TrainConfig) -> Result<Self> {
+        let log = match config.metrics {
+            // A resumed run continues the log it started
+            Some(ref path) if config.resume.is_some() => Some(MetricsLog::append(path)?),
+            Some(ref path) => Some(MetricsLog::create(path)?),
+            None => None,
+        };
+        let events = match config.tensorboard {
+            Some(ref dir) => Some(EventWriter::create(dir)?),
+            None => None,
+        };
+        Ok(Self { log, events })
+    }
+
+    fn record(&mut self, metrics: &EpisodeMetrics) {
+        if let Some(ref mut log) = self.log {
+            log.record(metrics).expect("Failed to write metrics");
+        }
+        if let Some(ref mut events) = self.events {
+            events
+                .add_metrics(metrics)
+                .expect("Failed to write TensorBoard events");
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Some(ref mut log) = self.log {
+            log.flush().expect("Failed to write metrics");
+        }
+        if let Some(ref mut events) = self.events {
+            events.flush().expect("Failed to write TensorBoard events");
+        }
+    }
 }
 
-fn run_episode(agent: &mut Agent) -> i32 {
-    let mut game = Game::new();
+/// Trains every configuration of a sweep with several seeds and ranks them
+fn run_sweep(args: Vec<String>) {
+    let (config, sweep) = match parse_sweep_args(args) {
+        Ok(configs) => configs,
+        Err(err) => {
+            eprintln!("{}", err);
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
 
-    for _step in 0..MAX_STEPS {
-        let state = get_state(&game);
-        let action = choose_action(agent, state);
+    let seed = config.seed.unwrap_or_else(rand::random);
+    let mut rng = SimRng::new(seed);
+    let base = Trial {
+        params: config.params.unwrap_or_default(),
+        encoder: config.encoder.clone().unwrap_or_default(),
+        label: String::new(),
+    };
+    let trials = sweep.trials(&base, &mut rng);
+    let storage = config.storage.unwrap_or_default();
+    for trial in &trials {
+        if let Err(err) = storage.check_states(trial.encoder.state_count()) {
+            eprintln!("{}", err);
+            process::exit(2);
+        }
+    }
+    // Every configuration trains with the same seeds, so they face the same courses
+    let mut seeds = seed;
+    let run_seeds: Vec<u64> = (0..sweep.seeds).map(|_| splitmix64(&mut seeds)).collect();
+    let runs: Vec<(usize, usize)> = (0..trials.len())
+        .flat_map(|trial| (0..run_seeds.len()).map(move |seed| (trial, seed)))
+        .collect();
 
-        do_action(&mut game, action);
-        game.scroll_course();
+    println!(
+        "=== Sweep ({} search, {} configurations × {} seeds, jobs: {}) ===",
+        sweep.search,
+        trials.len(),
+        sweep.seeds,
+        sweep.jobs
+    );
+    println!("Seed: {}\n", seed);
 
-        let reward = get_reward(&game);
-        let next_state = get_state(&game);
+    let suite = EvalSuite::new(config.eval_seed, config.eval_courses, config.max_steps);
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+    let mut evaluations = Vec::with_capacity(runs.len());
+    // Best run of each configuration
+    let mut best_runs: Vec<Option<(f64, Agent)>> = vec![None; trials.len()];
 
-        update_q(agent, state, action, reward, next_state);
+    thread::scope(|scope| {
+        for _ in 0..sweep.jobs.min(runs.len()) {
+            let sender = sender.clone();
+            let (config, trials, runs, suite, next) = (&config, &trials, &runs, &suite, &next);
+            let run_seeds = &run_seeds;
+            scope.spawn(move || {
+                while let Some(&(trial, seed)) = runs.get(next.fetch_add(1, Ordering::SeqCst)) {
+                    let agent = train_trial(config, &trials[trial], run_seeds[seed]);
+                    let evaluation = suite.evaluate(&agent);
+                    if sender.send((trial, seed, evaluation, agent)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
 
-        if game.has_collision() {
-            break;
+        for (done, (trial, seed, evaluation, agent)) in receiver.iter().enumerate() {
+            println!(
+                "[{:>3}/{}] {} (seed {}): mean {:.1}, crashes {:.1}%",
+                done + 1,
+                runs.len(),
+                describe_trial(&trials[trial]),
+                seed + 1,
+                evaluation.mean(),
+                evaluation.crash_rate() * 100.0
+            );
+            let best = &mut best_runs[trial];
+            if best
+                .as_ref()
+                .is_none_or(|(mean, _)| evaluation.mean() > *mean)
+            {
+                *best = Some((evaluation.mean(), agent));
+            }
+            evaluations.push((trial, evaluation));
         }
+    });
+
+    let rankings = rank(&evaluations);
+    println!("\n=== Sweep Results ===");
+    println!("Rank     Mean    ± SD  Crashes  Settings");
+    for (i, ranking) in rankings.iter().enumerate() {
+        println!(
+            "{:>4}  {:7.1}  {:6.1}  {:6.1}%  {}",
+            i + 1,
+            ranking.mean(),
+            ranking.std_dev(),
+            ranking.crash_rate * 100.0,
+            describe_trial(&trials[ranking.trial])
+        );
     }
 
-    if game.distance > agent.best_score {
-        agent.best_score = game.distance;
+    let best = rankings
+        .first()
+        .and_then(|ranking| best_runs[ranking.trial].take());
+    if let Some((mean, agent)) = best {
+        agent.save(&config.output).expect("Failed to save data");
+        println!(
+            "\nBest run of the top configuration ({:.1} mean) saved to {}",
+            mean, config.output
+        );
     }
-    agent.episodes += 1;
+}
 
-    game.distance
+/// Reads the base run settings and the sweep's own settings
+fn parse_sweep_args(args: Vec<String>) -> Result<(TrainConfig, SweepConfig)> {
+    let mut sweep = SweepConfig::default();
+    let mut run_args = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.strip_prefix("--") {
+            Some(key) if SweepConfig::KEYS.contains(&key) => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| GameError::InvalidConfig(format!("{} needs a value", arg)))?;
+                sweep.set(key, &value)?;
+            }
+            _ => run_args.push(arg),
+        }
+    }
+    let config = parse_args(run_args)?;
+    if config.resume.is_some() {
+        return Err(GameError::InvalidConfig(
+            "a sweep can't resume a checkpoint".to_string(),
+        ));
+    }
+    Ok((config, sweep))
+}
+
+/// Trains one run of a sweep from scratch, without reports or checkpoints
+fn train_trial(config: &TrainConfig, trial: &Trial, seed: u64) -> Agent {
+    let mut seeds = seed;
+    let mut agent = Agent::with_storage(
+        trial.encoder.clone(),
+        trial.params,
+        config.storage.unwrap_or_default(),
+    );
+    agent.seed(splitmix64(&mut seeds));
+    let mut courses = SimRng::new(splitmix64(&mut seeds));
+    config.learner.prepare(&mut agent);
+    let mut credit = config.credit();
+    for _ in 0..config.episodes {
+        train_episode(
+            &mut agent,
+            config.learner,
+            &mut credit,
+            &mut courses,
+            config.max_steps,
+        );
+    }
+    agent
+}
+
+fn describe_trial(trial: &Trial) -> &str {
+    if trial.label.is_empty() {
+        "base settings"
+    } else {
+        &trial.label
+    }
+}
+
+fn save_checkpoint(agent: &Agent, courses: &SimRng, config: &TrainConfig) {
+    agent
+        .save_checkpoint(&config.checkpoint, courses, &config.resume_settings())
+        .expect("Failed to save checkpoint");
+}
+
+/// Reads the config file, if given, then applies the command-line settings
+fn parse_args(args: Vec<String>) -> Result<TrainConfig> {
+    let mut config = match args.iter().position(|arg| arg == "--config") {
+        Some(i) => {
+            let path = args
+                .get(i + 1)
+                .ok_or_else(|| GameError::InvalidConfig("--config needs a value".to_string()))?;
+            TrainConfig::load(path)?
+        }
+        None => TrainConfig::default(),
+    };
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let Some(key) = arg.strip_prefix("--") else {
+            return Err(GameError::InvalidConfig(format!(
+                "unknown argument: {}",
+                arg
+            )));
+        };
+        if key == "config" {
+            args.next();
+            continue;
+        }
+        let value = if TrainConfig::SWITCHES.contains(&key) {
+            "true".to_string()
+        } else {
+            args.next()
+                .ok_or_else(|| GameError::InvalidConfig(format!("{} needs a value", arg)))?
+        };
+        config.set(key, &value)?;
+    }
+
+    config.validate()?;
+    Ok(config)
+}
+
+fn format_evaluation(evaluation: &Evaluation) -> String {
+    let (low, high) = evaluation.mean_interval();
+    format!(
+        "  Evaluation   | Mean: {:7.1} ({:.1}-{:.1}) | Median: {:5} | Crashes: {:5.1}%",
+        evaluation.mean(),
+        low,
+        high,
+        evaluation.median(),
+        evaluation.crash_rate() * 100.0
+    )
+}
+
+fn describe_credit(credit: &CreditAssignment) -> String {
+    match credit {
+        CreditAssignment::OneStep => "one-step".to_string(),
+        CreditAssignment::NStep(buffer) => format!("{}-step", buffer.n()),
+        CreditAssignment::Lambda(traces) => {
+            format!("{} Q(λ={})", traces.kind(), traces.lambda())
+        }
+    }
 }
 
 struct TrainingStats {
@@ -97,13 +665,14 @@ impl TrainingStats {
         }
     }
 
-    fn print_progress_with_agent(&self, episode: usize, agent: &Agent) {
-        println!(
-            "Episode {:5} | Best: {:5} | Avg: {:7.1} | ε: {:.3}",
+    fn progress_line(&self, episode: usize, agent: &Agent) -> String {
+        format!(
+            "Episode {:5} | Best: {:5} | Avg: {:7.1} | ε: {:.3} | α: {:.3}",
             episode,
             agent.best_score,
             self.average(),
-            agent.epsilon
-        );
+            agent.epsilon,
+            agent.alpha
+        )
     }
 }
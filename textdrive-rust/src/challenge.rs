@@ -0,0 +1,308 @@
+use crate::error::{GameError, Result};
+use crate::game::SCROLL_DELAY_MS;
+use crate::rng::splitmix64;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Crockford base32 alphabet (no I, L, O or U)
+const CODE_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+const CODE_CHARS: usize = 10;
+const CODE_GROUP: usize = 5;
+const DELAY_UNIT_MS: u64 = 10;
+const DAILY_SALT: u64 = 0x7465_7874_6472_6976; // "textdriv"
+
+/// A calendar date (proleptic Gregorian, UTC)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    /// Creates a date, returning `None` if it doesn't exist
+    pub fn new(year: i32, month: u32, day: u32) -> Option<Self> {
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            return None;
+        }
+        Some(Self { year, month, day })
+    }
+
+    /// Gets the current date in UTC
+    ///
+    /// UTC is used so everyone gets the same daily challenge at the same time.
+    pub fn today() -> Self {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Self::from_days((secs / 86_400) as i64)
+    }
+
+    /// Converts a count of days since 1970-01-01 into a date
+    pub fn from_days(days: i64) -> Self {
+        // Howard Hinnant's civil_from_days
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = (yoe + era * 400 + i64::from(month <= 2)) as i32;
+        Self { year, month, day }
+    }
+
+    /// Converts the date into a count of days since 1970-01-01
+    pub fn to_days(self) -> i64 {
+        let year = i64::from(self.year) - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let yoe = year.rem_euclid(400);
+        let month = i64::from(self.month);
+        let mp = if month > 2 { month - 3 } else { month + 9 };
+        let doy = (153 * mp + 2) / 5 + i64::from(self.day) - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        era * 146_097 + doe - 719_468
+    }
+
+    /// Day of the week, 0 = Monday through 6 = Sunday
+    pub fn weekday(self) -> u32 {
+        // 1970-01-01 was a Thursday
+        (self.to_days() + 3).rem_euclid(7) as u32
+    }
+
+    /// First day of the month this date is in
+    pub fn first_of_month(self) -> Self {
+        Self { day: 1, ..self }
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl FromStr for Date {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || GameError::InvalidChallengeData(format!("invalid date: {}", s));
+        let mut parts = s.splitn(3, '-');
+        let mut next = || parts.next().ok_or_else(invalid);
+        let year = next()?.parse().map_err(|_| invalid())?;
+        let month = next()?.parse().map_err(|_| invalid())?;
+        let day = next()?.parse().map_err(|_| invalid())?;
+        Date::new(year, month, day).ok_or_else(invalid)
+    }
+}
+
+/// Number of days in the given month
+pub fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        _ => 0,
+    }
+}
+
+/// Derives the course seed for the daily challenge on the given date
+pub fn daily_seed(date: Date) -> u32 {
+    let mut state = DAILY_SALT ^ date.to_days() as u64;
+    splitmix64(&mut state) as u32
+}
+
+/// Short, shareable identifier of a course
+///
+/// A code encodes the seed and the scroll speed, so everyone who enters the
+/// same code plays the same track at the same speed. Codes are ten base32
+/// characters split into two groups of five.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CourseCode {
+    pub seed: u32,
+    pub scroll_delay_ms: u64,
+}
+
+impl CourseCode {
+    /// Creates a code for the given seed at the default speed
+    pub fn new(seed: u32) -> Self {
+        Self {
+            seed,
+            scroll_delay_ms: SCROLL_DELAY_MS,
+        }
+    }
+
+    /// Gets the code of the daily challenge on the given date
+    pub fn daily(date: Date) -> Self {
+        Self::new(daily_seed(date))
+    }
+
+    /// Encodes the course as a code string
+    ///
+    /// The scroll delay is stored in 10 ms steps and clamped to 10..=2550 ms.
+    pub fn encode(&self) -> String {
+        let delay = (self.scroll_delay_ms / DELAY_UNIT_MS).clamp(1, 255);
+        let payload = (u64::from(self.seed) << 8) | delay;
+        let value = (payload << 8) | checksum(payload);
+
+        let mut code = String::with_capacity(CODE_CHARS + 1);
+        for i in (0..CODE_CHARS).rev() {
+            let digit = (value >> (i * 5)) & 0x1F;
+            code.push(CODE_ALPHABET[digit as usize] as char);
+            if i == CODE_GROUP {
+                code.push('-');
+            }
+        }
+        code
+    }
+
+    /// Decodes a code string
+    ///
+    /// Decoding ignores case and dashes, and accepts the usual Crockford
+    /// substitutions (`I`/`L` for `1`, `O` for `0`).
+    pub fn decode(code: &str) -> Result<Self> {
+        let invalid = |msg: &str| GameError::InvalidCourseCode(format!("{}: {}", msg, code));
+
+        let mut value: u64 = 0;
+        let mut count = 0;
+        for c in code.chars().filter(|&c| c != '-') {
+            let c = match c.to_ascii_uppercase() {
+                'I' | 'L' => '1',
+                'O' => '0',
+                c => c,
+            };
+            let digit = CODE_ALPHABET
+                .iter()
+                .position(|&a| a as char == c)
+                .ok_or_else(|| invalid("invalid character"))?;
+            value = (value << 5) | digit as u64;
+            count += 1;
+            if count > CODE_CHARS {
+                return Err(invalid("code too long"));
+            }
+        }
+        if count != CODE_CHARS {
+            return Err(invalid("code too short"));
+        }
+
+        let payload = value >> 8;
+        if payload >> 40 != 0 || checksum(payload) != value & 0xFF {
+            return Err(invalid("checksum mismatch"));
+        }
+        let delay = payload & 0xFF;
+        if delay == 0 {
+            return Err(invalid("invalid speed"));
+        }
+
+        Ok(Self {
+            seed: (payload >> 8) as u32,
+            scroll_delay_ms: delay * DELAY_UNIT_MS,
+        })
+    }
+}
+
+impl fmt::Display for CourseCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.encode())
+    }
+}
+
+impl FromStr for CourseCode {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::decode(s)
+    }
+}
+
+fn checksum(payload: u64) -> u64 {
+    let mut state = payload;
+    splitmix64(&mut state) & 0xFF
+}
+
+/// Daily challenge scores, one per date
+///
+/// Stored as a plain text file with one `YYYY-MM-DD score` line per date.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChallengeHistory {
+    results: BTreeMap<Date, i32>,
+}
+
+impl ChallengeHistory {
+    /// Creates an empty history
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the history from a file, returning an empty one if it doesn't exist
+    pub fn load(filename: &str) -> Result<Self> {
+        let text = match fs::read_to_string(filename) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::new()),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut history = Self::new();
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let (date, score) = line.split_once(' ').ok_or_else(|| {
+                GameError::InvalidChallengeData(format!("malformed line: {}", line))
+            })?;
+            let score = score
+                .trim()
+                .parse()
+                .map_err(|_| GameError::InvalidChallengeData(format!("invalid score: {}", line)))?;
+            history.results.insert(date.parse()?, score);
+        }
+        Ok(history)
+    }
+
+    /// Saves the history to a file
+    pub fn save(&self, filename: &str) -> Result<()> {
+        let text: String = self
+            .results
+            .iter()
+            .map(|(date, score)| format!("{} {}\n", date, score))
+            .collect();
+        fs::write(filename, text)?;
+        Ok(())
+    }
+
+    /// Records the scored attempt for a date
+    ///
+    /// Only the first attempt counts; returns false if the date already has a
+    /// result.
+    pub fn record(&mut self, date: Date, score: i32) -> bool {
+        if self.results.contains_key(&date) {
+            return false;
+        }
+        self.results.insert(date, score);
+        true
+    }
+
+    /// Gets the score recorded for a date
+    pub fn get(&self, date: Date) -> Option<i32> {
+        self.results.get(&date).copied()
+    }
+
+    /// Returns true if the challenge for the date has already been played
+    pub fn has_played(&self, date: Date) -> bool {
+        self.results.contains_key(&date)
+    }
+
+    /// Iterates over all results in date order
+    pub fn iter(&self) -> impl Iterator<Item = (Date, i32)> + '_ {
+        self.results.iter().map(|(&date, &score)| (date, score))
+    }
+
+    /// Best score over all dates
+    pub fn best(&self) -> Option<(Date, i32)> {
+        self.iter().max_by_key(|&(_, score)| score)
+    }
+}
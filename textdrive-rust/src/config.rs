@@ -0,0 +1,341 @@
+use crate::encoder::{Encoder, StateEncoder};
+use crate::error::{GameError, Result};
+use crate::evaluation::{DEFAULT_SUITE_COURSES, DEFAULT_SUITE_SEED};
+use crate::export::Precision;
+use crate::hyperparams::Hyperparams;
+use crate::learner::Learner;
+use crate::metrics::MetricsFormat;
+use crate::model;
+use crate::parallel::DEFAULT_SYNC_EVERY;
+use crate::stopping::EarlyStopping;
+use crate::storage::StorageKind;
+use crate::traces::{CreditAssignment, EligibilityTraces, NStepBuffer, TraceKind};
+use std::fmt;
+use std::fs;
+use std::str::FromStr;
+
+/// Settings of a training run
+///
+/// Every setting has a key, used both in TOML config files and as a
+/// command-line flag: `episodes = 20000` in a file is `--episodes 20000` on
+/// the command line. Settings the run can't change after a checkpoint are
+/// `None` until given, so resuming can reject them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrainConfig {
+    pub episodes: usize,
+    /// Steps after which an episode ends even without a crash
+    pub max_steps: usize,
+    /// Episodes between progress reports
+    pub report_interval: usize,
+    /// Episodes the reported average covers
+    pub recent_window: usize,
+    /// Where the trained model is saved
+    pub output: String,
+    pub checkpoint: String,
+    /// Episodes between checkpoints
+    pub checkpoint_every: usize,
+    /// Checkpoint to continue from
+    pub resume: Option<String>,
+    /// Episodes between greedy evaluations, 0 for none
+    pub eval_every: usize,
+    /// Courses in the evaluation suite
+    pub eval_courses: usize,
+    /// Seed of the evaluation suite
+    pub eval_seed: u64,
+    /// Where the best-evaluated agent is saved, as a model
+    pub best: String,
+    /// CSV or JSONL file to write per-episode metrics to
+    pub metrics: Option<String>,
+    /// Directory to write TensorBoard event files to
+    pub tensorboard: Option<String>,
+    /// Show a live dashboard instead of progress lines
+    pub tui: bool,
+    /// Threads training in parallel
+    pub workers: usize,
+    /// Episodes each worker runs between merges
+    pub sync_every: usize,
+    /// Mean evaluation score to stop at
+    pub target_score: Option<f64>,
+    /// Evaluations without improvement to stop after
+    pub patience: Option<usize>,
+    /// Q-value change per episode to stop below
+    pub min_q_change: Option<f64>,
+    pub seed: Option<u64>,
+    pub learner: Learner,
+    pub n_step: Option<usize>,
+    pub lambda: Option<f64>,
+    pub trace: TraceKind,
+    pub encoder: Option<Encoder>,
+    pub storage: Option<StorageKind>,
+    /// Hyperparameters and rewards, if any was given
+    pub params: Option<Hyperparams>,
+    /// Where to export a compact policy after training
+    pub export: Option<String>,
+    pub precision: Precision,
+    pub compress: bool,
+}
+
+impl Default for TrainConfig {
+    fn default() -> Self {
+        Self {
+            episodes: 50000,
+            max_steps: 10000,
+            report_interval: 500,
+            recent_window: 500,
+            output: "qtable.bin".to_string(),
+            checkpoint: "checkpoint.bin".to_string(),
+            checkpoint_every: 1000,
+            resume: None,
+            eval_every: 5000,
+            eval_courses: DEFAULT_SUITE_COURSES,
+            eval_seed: DEFAULT_SUITE_SEED,
+            best: "best.bin".to_string(),
+            metrics: None,
+            tensorboard: None,
+            tui: false,
+            workers: 1,
+            sync_every: DEFAULT_SYNC_EVERY,
+            target_score: None,
+            patience: None,
+            min_q_change: None,
+            seed: None,
+            learner: Learner::default(),
+            n_step: None,
+            lambda: None,
+            trace: TraceKind::Watkins,
+            encoder: None,
+            storage: None,
+            params: None,
+            export: None,
+            precision: Precision::I8,
+            compress: false,
+        }
+    }
+}
+
+impl TrainConfig {
+    /// Keys that take no value on the command line
+    pub const SWITCHES: [&'static str; 2] = ["tui", "compress"];
+
+    /// Reads a TOML config file on top of the defaults
+    pub fn load(filename: &str) -> Result<Self> {
+        let mut config = Self::default();
+        config.apply_toml(&fs::read_to_string(filename)?)?;
+        Ok(config)
+    }
+
+    /// Applies the top-level keys of a TOML document
+    pub fn apply_toml(&mut self, text: &str) -> Result<()> {
+        let table: toml::Table = text
+            .parse()
+            .map_err(|err| GameError::InvalidConfig(format!("config file: {}", err)))?;
+        for (key, value) in table {
+            let value = match value {
+                toml::Value::String(text) => text,
+                toml::Value::Integer(_) | toml::Value::Float(_) | toml::Value::Boolean(_) => {
+                    value.to_string()
+                }
+                _ => {
+                    return Err(GameError::InvalidConfig(format!(
+                        "config file: {} must be a string, number or boolean",
+                        key
+                    )))
+                }
+            };
+            self.set(&key, &value)?;
+        }
+        Ok(())
+    }
+
+    /// Sets one setting from its text form
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        match key {
+            "episodes" => self.episodes = parse_at_least(key, value, 1)?,
+            "max-steps" => self.max_steps = parse_at_least(key, value, 1)?,
+            "report-interval" => self.report_interval = parse_at_least(key, value, 1)?,
+            "recent-window" => self.recent_window = parse_at_least(key, value, 1)?,
+            "output" => self.output = value.to_string(),
+            "checkpoint" => self.checkpoint = value.to_string(),
+            "checkpoint-every" => self.checkpoint_every = parse_at_least(key, value, 1)?,
+            "resume" => self.resume = Some(value.to_string()),
+            "eval-every" => self.eval_every = parse(key, value)?,
+            "eval-courses" => self.eval_courses = parse_at_least(key, value, 1)?,
+            "eval-seed" => self.eval_seed = parse(key, value)?,
+            "best" => self.best = value.to_string(),
+            "metrics" => {
+                MetricsFormat::from_path(value)?;
+                self.metrics = Some(value.to_string());
+            }
+            "tensorboard" => self.tensorboard = Some(value.to_string()),
+            "tui" => self.tui = parse(key, value)?,
+            "workers" => self.workers = parse_at_least(key, value, 1)?,
+            "sync-every" => self.sync_every = parse_at_least(key, value, 1)?,
+            "target-score" => self.target_score = Some(parse(key, value)?),
+            "patience" => self.patience = Some(parse_at_least(key, value, 1)?),
+            "min-q-change" => self.min_q_change = Some(parse(key, value)?),
+            "seed" => self.seed = Some(parse(key, value)?),
+            "learner" => self.learner = value.parse()?,
+            "n-step" => self.n_step = Some(parse_at_least(key, value, 1)?),
+            "lambda" => self.lambda = Some(parse(key, value)?),
+            "trace" => self.trace = value.parse()?,
+            "encoder" => self.encoder = Some(value.parse()?),
+            "storage" => self.storage = Some(value.parse()?),
+            "alpha" => self.params_mut().alpha = value.parse()?,
+            "epsilon" => self.params_mut().epsilon = value.parse()?,
+            "gamma" => self.params_mut().gamma = parse(key, value)?,
+            "collision-reward" => self.params_mut().collision_reward = parse(key, value)?,
+            "survival-reward" => self.params_mut().survival_reward = parse(key, value)?,
+            "export" => self.export = Some(value.to_string()),
+            "precision" => self.precision = value.parse()?,
+            "compress" => self.compress = parse(key, value)?,
+            _ => {
+                return Err(GameError::InvalidConfig(format!(
+                    "unknown setting: {}",
+                    key
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the settings work together
+    pub fn validate(&self) -> Result<()> {
+        if self.n_step.is_some() && self.lambda.is_some() {
+            return Err(GameError::InvalidConfig(
+                "n-step and lambda can't be combined".to_string(),
+            ));
+        }
+        if self
+            .lambda
+            .is_some_and(|lambda| !(0.0..=1.0).contains(&lambda))
+        {
+            return Err(GameError::InvalidConfig(
+                "lambda must be between 0 and 1".to_string(),
+            ));
+        }
+        let multi_step = self.n_step.is_some() || self.lambda.is_some();
+        if multi_step && self.learner == Learner::DoubleQ {
+            return Err(GameError::InvalidConfig(
+                "double-q only supports one-step updates".to_string(),
+            ));
+        }
+        if self.lambda.is_some() && self.learner != Learner::QLearning {
+            return Err(GameError::InvalidConfig(
+                "lambda is Q(λ) and needs the q-learning learner".to_string(),
+            ));
+        }
+        if self.eval_every == 0 && (self.target_score.is_some() || self.patience.is_some()) {
+            return Err(GameError::InvalidConfig(
+                "target-score and patience need evaluations (eval-every)".to_string(),
+            ));
+        }
+        if let Some(ref encoder) = self.encoder {
+            self.storage
+                .unwrap_or_default()
+                .check_states(encoder.state_count())?;
+        }
+        let fixed = self.encoder.is_some()
+            || self.storage.is_some()
+            || self.seed.is_some()
+            || self.params.is_some();
+        if self.resume.is_some() && fixed {
+            return Err(GameError::InvalidConfig(
+                "encoder, storage, seed and hyperparameters come from the checkpoint when resuming"
+                    .to_string(),
+            ));
+        }
+        if let Some(ref path) = self.resume {
+            let (info, _) = model::parse_chunked(&fs::read(path)?)?;
+            match info.settings {
+                Some(saved) if saved != self.resume_settings() => {
+                    return Err(GameError::InvalidConfig(format!(
+                        "{} was trained with {}; resume with the same settings",
+                        path, saved
+                    )))
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Settings a checkpoint of this run records
+    pub fn resume_settings(&self) -> ResumeSettings {
+        ResumeSettings {
+            learner: self.learner,
+            n_step: self.n_step,
+            lambda: self.lambda,
+            trace: self.lambda.map(|_| self.trace),
+            max_steps: self.max_steps,
+        }
+    }
+
+    /// How TD errors are assigned to earlier states
+    pub fn credit(&self) -> CreditAssignment {
+        match (self.n_step, self.lambda) {
+            (Some(n), _) => CreditAssignment::NStep(NStepBuffer::new(n)),
+            (None, Some(lambda)) => {
+                CreditAssignment::Lambda(EligibilityTraces::new(lambda, self.trace))
+            }
+            (None, None) => CreditAssignment::OneStep,
+        }
+    }
+
+    /// Early stopping criteria of the run
+    pub fn early_stopping(&self) -> EarlyStopping {
+        let mut stopping = EarlyStopping::default();
+        stopping.target_score = self.target_score;
+        stopping.patience = self.patience;
+        stopping.min_q_change = self.min_q_change;
+        stopping
+    }
+
+    fn params_mut(&mut self) -> &mut Hyperparams {
+        self.params.get_or_insert_with(Hyperparams::default)
+    }
+}
+
+/// Settings a resumed run has to share with the run that saved the checkpoint
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResumeSettings {
+    pub learner: Learner,
+    pub n_step: Option<usize>,
+    pub lambda: Option<f64>,
+    /// Kind of traces, if lambda is set
+    pub trace: Option<TraceKind>,
+    pub max_steps: usize,
+}
+
+/// Formats as the command-line flags that select the settings
+impl fmt::Display for ResumeSettings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "--learner {}", self.learner)?;
+        if let Some(n) = self.n_step {
+            write!(f, " --n-step {}", n)?;
+        }
+        if let Some(lambda) = self.lambda {
+            write!(f, " --lambda {}", lambda)?;
+        }
+        if let Some(trace) = self.trace {
+            write!(f, " --trace {}", trace)?;
+        }
+        write!(f, " --max-steps {}", self.max_steps)
+    }
+}
+
+fn parse<T: FromStr>(key: &str, value: &str) -> Result<T> {
+    value
+        .parse()
+        .map_err(|_| GameError::InvalidConfig(format!("invalid value for {}: {}", key, value)))
+}
+
+fn parse_at_least(key: &str, value: &str, min: usize) -> Result<usize> {
+    let number = parse(key, value)?;
+    if number < min {
+        return Err(GameError::InvalidConfig(format!(
+            "{} must be at least {}",
+            key, min
+        )));
+    }
+    Ok(number)
+}
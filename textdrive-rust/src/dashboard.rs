@@ -0,0 +1,196 @@
+use crate::game::Game;
+use crate::metrics::EpisodeMetrics;
+use crate::policy::Policy;
+use crate::qlearning::{do_action, Agent};
+use crate::render::{draw_game_field_at, sparkline, FIELD_WIDTH};
+use crate::rng::splitmix64;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Time between frames, also the pace of the preview game
+pub const FRAME_INTERVAL: Duration = Duration::from_millis(150);
+/// Bars in each sparkline
+const SPARK_WIDTH: usize = 30;
+/// Log lines kept below the charts
+const MESSAGE_LINES: usize = 6;
+/// Column the preview board starts at
+const PREVIEW_X: i32 = 58;
+
+/// Live view of a training run
+///
+/// The trainer records each episode; every [`FRAME_INTERVAL`] the dashboard
+/// redraws its charts and moves a preview game one step, driven greedily by
+/// the agent as it is at that moment.
+pub struct Dashboard {
+    episodes: usize,
+    latest: EpisodeMetrics,
+    lengths: VecDeque<f64>,
+    eval_means: VecDeque<f64>,
+    /// Sampled once per frame
+    epsilons: VecDeque<f64>,
+    alphas: VecDeque<f64>,
+    throughputs: VecDeque<f64>,
+    messages: VecDeque<String>,
+    last_frame: Instant,
+    /// Episode count and time throughput was last measured at
+    mark: (u64, Instant),
+    preview: Game,
+    preview_seeds: u64,
+    preview_steps: usize,
+    max_steps: usize,
+}
+
+impl Dashboard {
+    /// Creates a dashboard for a run of `episodes` episodes; the preview
+    /// plays courses derived from `preview_seed`
+    pub fn new(episodes: usize, preview_seed: u64, max_steps: usize) -> Self {
+        let mut preview_seeds = preview_seed;
+        let now = Instant::now();
+        Self {
+            episodes,
+            latest: EpisodeMetrics::default(),
+            lengths: VecDeque::new(),
+            eval_means: VecDeque::new(),
+            epsilons: VecDeque::new(),
+            alphas: VecDeque::new(),
+            throughputs: VecDeque::new(),
+            messages: VecDeque::new(),
+            last_frame: now,
+            mark: (0, now),
+            preview: Game::with_seed(splitmix64(&mut preview_seeds)),
+            preview_seeds,
+            preview_steps: 0,
+            max_steps,
+        }
+    }
+
+    /// Records the metrics of a finished episode
+    pub fn record(&mut self, metrics: &EpisodeMetrics) {
+        if self.mark.0 == 0 {
+            self.mark.0 = metrics.episode.saturating_sub(1);
+        }
+        push(&mut self.lengths, metrics.steps as f64);
+        if let Some(eval) = metrics.evaluation {
+            push(&mut self.eval_means, eval.mean);
+        }
+        self.latest = metrics.clone();
+    }
+
+    /// Adds a line to the log below the charts
+    pub fn message(&mut self, text: String) {
+        if self.messages.len() == MESSAGE_LINES {
+            self.messages.pop_front();
+        }
+        self.messages.push_back(text);
+    }
+
+    /// Whether a frame is due
+    pub fn due(&self) -> bool {
+        self.last_frame.elapsed() >= FRAME_INTERVAL
+    }
+
+    /// Moves the preview one step and redraws everything
+    pub fn draw(&mut self, window: &pancurses::Window, agent: &Agent) {
+        self.last_frame = Instant::now();
+        self.sample(agent);
+        self.step_preview(agent);
+
+        window.erase();
+        let latest = &self.latest;
+        window.mvprintw(
+            0,
+            0,
+            format!(
+                "Training  Episode {} / {}  Best: {}  [Q] Stop",
+                latest.episode, self.episodes, agent.best_score
+            ),
+        );
+
+        let coverage = latest.states_visited as f64 / agent.q.len().max(1) as f64;
+        let lines = [
+            chart("Episode length", &self.lengths, latest.steps as f64, 0),
+            match self.eval_means.back() {
+                Some(&mean) => chart("Eval mean", &self.eval_means, mean, 1),
+                None => "Eval mean       (none yet)".to_string(),
+            },
+            chart("Epsilon", &self.epsilons, latest.epsilon, 3),
+            chart("Alpha", &self.alphas, latest.alpha, 3),
+            chart("Episodes/s", &self.throughputs, self.throughput(), 0),
+            format!(
+                "Coverage        {:.1}% ({} / {} states)",
+                coverage * 100.0,
+                latest.states_visited,
+                agent.q.len()
+            ),
+        ];
+        for (row, line) in lines.iter().enumerate() {
+            window.mvprintw(2 + row as i32 * 2, 0, line);
+        }
+        for (row, text) in self.messages.iter().enumerate() {
+            window.mvprintw(15 + row as i32, 0, text);
+        }
+
+        window.mvprintw(
+            0,
+            PREVIEW_X,
+            format!("Greedy preview: {:>5}", self.preview.distance),
+        );
+        draw_game_field_at(window, &self.preview, PREVIEW_X);
+        window.mvprintw(1, PREVIEW_X, "-".repeat(FIELD_WIDTH as usize));
+
+        window.refresh();
+    }
+
+    /// Episodes per second since the last measurement
+    fn throughput(&self) -> f64 {
+        self.throughputs.back().copied().unwrap_or(0.0)
+    }
+
+    /// Samples the values charted per frame
+    fn sample(&mut self, agent: &Agent) {
+        push(&mut self.epsilons, agent.epsilon);
+        push(&mut self.alphas, agent.alpha);
+
+        let (episodes, since) = self.mark;
+        let elapsed = since.elapsed().as_secs_f64();
+        if elapsed >= 1.0 {
+            let done = self.latest.episode.saturating_sub(episodes);
+            push(&mut self.throughputs, done as f64 / elapsed);
+            self.mark = (self.latest.episode, Instant::now());
+        }
+    }
+
+    /// Advances the preview game, starting the next course after a crash
+    fn step_preview(&mut self, agent: &Agent) {
+        if self.preview.has_collision() || self.preview_steps >= self.max_steps {
+            self.preview = Game::with_seed(splitmix64(&mut self.preview_seeds));
+            self.preview_steps = 0;
+            return;
+        }
+        let action = agent.best_action(agent.state(&self.preview));
+        do_action(&mut self.preview, action);
+        self.preview.scroll_course();
+        self.preview_steps += 1;
+    }
+}
+
+/// Appends a value, keeping only as many as a sparkline shows
+fn push(values: &mut VecDeque<f64>, value: f64) {
+    if values.len() == SPARK_WIDTH {
+        values.pop_front();
+    }
+    values.push_back(value);
+}
+
+/// Label, sparkline and current value on one line
+fn chart(label: &str, values: &VecDeque<f64>, current: f64, decimals: usize) -> String {
+    let values: Vec<f64> = values.iter().copied().collect();
+    format!(
+        "{:<15} {:<width$} {:.*}",
+        label,
+        sparkline(&values, SPARK_WIDTH),
+        decimals,
+        current,
+        width = SPARK_WIDTH
+    )
+}
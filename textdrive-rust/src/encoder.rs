@@ -0,0 +1,375 @@
+use crate::error::GameError;
+use crate::game::{Game, COLS_COUNT, PLAYER_ROW, ROWS_COUNT};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+
+/// Turns a game into a discrete state index for the Q-table
+pub trait StateEncoder {
+    /// Encodes the game into a state in `0..self.state_count()`
+    fn encode(&self, game: &Game) -> usize;
+
+    /// Number of distinct states this encoder produces
+    fn state_count(&self) -> usize;
+
+    /// Identity stored with saved models, e.g. `window:3x2`
+    fn id(&self) -> String;
+}
+
+/// Bit per cell of a window ahead of the car
+///
+/// `ahead` rows above the car, `range` cells to each side. The default 3×2
+/// window packs 15 cells into 15 bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowEncoder {
+    pub ahead: usize,
+    pub range: usize,
+}
+
+impl Default for WindowEncoder {
+    fn default() -> Self {
+        Self { ahead: 3, range: 2 }
+    }
+}
+
+impl WindowEncoder {
+    /// Decodes a state back into its window of walls
+    ///
+    /// `walls[row][col]` is row `row + 1` above the car (nearest first) and
+    /// column `col - range` relative to it.
+    pub fn decode(&self, state: usize) -> Vec<Vec<bool>> {
+        let width = 2 * self.range + 1;
+        (0..self.ahead)
+            .map(|row| {
+                (0..width)
+                    .map(|col| state >> (row * width + col) & 1 == 1)
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+impl StateEncoder for WindowEncoder {
+    /// Cuts each row's window out of its wall mask with one shift
+    fn encode(&self, game: &Game) -> usize {
+        let width = 2 * self.range + 1;
+        let window = (1u64 << width) - 1;
+        let mut state = 0;
+
+        for row in 0..self.ahead.min(PLAYER_ROW) {
+            let y = PLAYER_ROW - 1 - row;
+            // Padding by `range` keeps columns left of the board empty
+            let padded = (game.row_bits(y) as u64) << self.range;
+            let cells = (padded >> game.player_x) & window;
+            state |= (cells as usize) << (row * width);
+        }
+
+        state
+    }
+
+    fn state_count(&self) -> usize {
+        1 << (self.ahead * (2 * self.range + 1))
+    }
+
+    fn id(&self) -> String {
+        format!("window:{}x{}", self.ahead, self.range)
+    }
+}
+
+/// Offset from the car to the centre of the nearest gap, per row ahead
+///
+/// Offsets are clamped to `±max_offset`, so each row contributes one of
+/// `2 * max_offset + 1` values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GapEncoder {
+    pub ahead: usize,
+    pub max_offset: usize,
+}
+
+impl Default for GapEncoder {
+    fn default() -> Self {
+        Self {
+            ahead: 3,
+            max_offset: 4,
+        }
+    }
+}
+
+impl StateEncoder for GapEncoder {
+    fn encode(&self, game: &Game) -> usize {
+        let base = 2 * self.max_offset + 1;
+        let max = self.max_offset as i32;
+        let mut state = 0;
+
+        for row in (0..self.ahead).rev() {
+            let y = PLAYER_ROW as i32 - 1 - row as i32;
+            let offset = gap_offset(game, y).clamp(-max, max);
+            state = state * base + (offset + max) as usize;
+        }
+
+        state
+    }
+
+    fn state_count(&self) -> usize {
+        (2 * self.max_offset + 1).pow(self.ahead as u32)
+    }
+
+    fn id(&self) -> String {
+        format!("gap:{}x{}", self.ahead, self.max_offset)
+    }
+}
+
+/// Free cells between the car and the nearest wall on each side, per row ahead
+///
+/// The board edge counts as a wall. Distances are clamped to `max_distance`;
+/// a wall in the car's own column gives 0 on both sides.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WallOffsetEncoder {
+    pub ahead: usize,
+    pub max_distance: usize,
+}
+
+impl Default for WallOffsetEncoder {
+    fn default() -> Self {
+        Self {
+            ahead: 3,
+            max_distance: 3,
+        }
+    }
+}
+
+impl StateEncoder for WallOffsetEncoder {
+    fn encode(&self, game: &Game) -> usize {
+        let base = self.max_distance + 1;
+        let mut state = 0;
+
+        for row in (0..self.ahead).rev() {
+            let y = PLAYER_ROW as i32 - 1 - row as i32;
+            let (left, right) = if is_wall_at(game, game.player_x as i32, y) {
+                (0, 0)
+            } else {
+                (
+                    free_cells(game, y, -1).min(self.max_distance),
+                    free_cells(game, y, 1).min(self.max_distance),
+                )
+            };
+            state = (state * base + left) * base + right;
+        }
+
+        state
+    }
+
+    fn state_count(&self) -> usize {
+        (self.max_distance + 1).pow(2 * self.ahead as u32)
+    }
+
+    fn id(&self) -> String {
+        format!("walls:{}x{}", self.ahead, self.max_distance)
+    }
+}
+
+/// One of the built-in encoders, or one plugged in with [`register`]
+#[derive(Clone)]
+pub enum Encoder {
+    Window(WindowEncoder),
+    Gap(GapEncoder),
+    WallOffset(WallOffsetEncoder),
+    Custom(Arc<dyn StateEncoder + Send + Sync>),
+}
+
+/// Encoders added with [`register`]
+static CUSTOM_ENCODERS: RwLock<Vec<Encoder>> = RwLock::new(Vec::new());
+
+/// Makes an encoder from outside the crate available by its id
+///
+/// Agents can use the returned encoder like a built-in one. Once registered,
+/// its id parses back into it, so models saved with it load again and the
+/// trainer accepts it for `--encoder`. Registering an id again replaces the
+/// earlier encoder.
+pub fn register(encoder: impl StateEncoder + Send + Sync + 'static) -> Encoder {
+    let encoder = Encoder::Custom(Arc::new(encoder));
+    let mut custom = CUSTOM_ENCODERS.write().unwrap_or_else(|e| e.into_inner());
+    custom.retain(|known| known.id() != encoder.id());
+    custom.push(encoder.clone());
+    encoder
+}
+
+impl Encoder {
+    /// Number of rows ahead of the car the encoder looks at, if known
+    pub fn rows_ahead(&self) -> Option<usize> {
+        match self {
+            Encoder::Window(encoder) => Some(encoder.ahead),
+            Encoder::Gap(encoder) => Some(encoder.ahead),
+            Encoder::WallOffset(encoder) => Some(encoder.ahead),
+            Encoder::Custom(_) => None,
+        }
+    }
+
+    fn inner(&self) -> &dyn StateEncoder {
+        match self {
+            Encoder::Window(encoder) => encoder,
+            Encoder::Gap(encoder) => encoder,
+            Encoder::WallOffset(encoder) => encoder,
+            Encoder::Custom(encoder) => encoder.as_ref(),
+        }
+    }
+}
+
+impl fmt::Debug for Encoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Encoder::Window(encoder) => f.debug_tuple("Window").field(encoder).finish(),
+            Encoder::Gap(encoder) => f.debug_tuple("Gap").field(encoder).finish(),
+            Encoder::WallOffset(encoder) => f.debug_tuple("WallOffset").field(encoder).finish(),
+            Encoder::Custom(encoder) => f.debug_tuple("Custom").field(&encoder.id()).finish(),
+        }
+    }
+}
+
+/// Encoders are the same when their ids are
+impl PartialEq for Encoder {
+    fn eq(&self, other: &Self) -> bool {
+        self.id() == other.id()
+    }
+}
+
+impl Eq for Encoder {}
+
+impl Default for Encoder {
+    fn default() -> Self {
+        Encoder::Window(WindowEncoder::default())
+    }
+}
+
+impl StateEncoder for Encoder {
+    fn encode(&self, game: &Game) -> usize {
+        self.inner().encode(game)
+    }
+
+    fn state_count(&self) -> usize {
+        self.inner().state_count()
+    }
+
+    fn id(&self) -> String {
+        self.inner().id()
+    }
+}
+
+impl fmt::Display for Encoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.id())
+    }
+}
+
+impl FromStr for Encoder {
+    type Err = GameError;
+
+    /// Parses an encoder id such as `window:3x2`, `gap:3x4` or `walls:3x3`
+    ///
+    /// The size may be omitted (`gap`) to use the encoder's defaults. Ids of
+    /// registered encoders parse too.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let custom = CUSTOM_ENCODERS.read().unwrap_or_else(|e| e.into_inner());
+        if let Some(encoder) = custom.iter().find(|encoder| encoder.id() == s) {
+            return Ok(encoder.clone());
+        }
+
+        let invalid = || GameError::InvalidConfig(format!("unknown encoder: {}", s));
+        let (kind, size) = match s.split_once(':') {
+            Some((kind, size)) => {
+                let (a, b) = size.split_once('x').ok_or_else(invalid)?;
+                let a: usize = a.parse().map_err(|_| invalid())?;
+                let b: usize = b.parse().map_err(|_| invalid())?;
+                (kind, Some((a, b)))
+            }
+            None => (s, None),
+        };
+
+        // Rows ahead and bits needed to index the states, computed without
+        // overflowing
+        let (encoder, ahead, bits) = match kind {
+            "window" => {
+                let e = size.map_or_else(WindowEncoder::default, |(ahead, range)| WindowEncoder {
+                    ahead,
+                    range,
+                });
+                let bits = (e.ahead * (2 * e.range + 1)) as f64;
+                (Encoder::Window(e), e.ahead, bits)
+            }
+            "gap" => {
+                let e = size.map_or_else(GapEncoder::default, |(ahead, max_offset)| GapEncoder {
+                    ahead,
+                    max_offset,
+                });
+                let bits = e.ahead as f64 * ((2 * e.max_offset + 1) as f64).log2();
+                (Encoder::Gap(e), e.ahead, bits)
+            }
+            "walls" => {
+                let e = size.map_or_else(WallOffsetEncoder::default, |(ahead, max_distance)| {
+                    WallOffsetEncoder {
+                        ahead,
+                        max_distance,
+                    }
+                });
+                let bits = 2.0 * e.ahead as f64 * ((e.max_distance + 1) as f64).log2();
+                (Encoder::WallOffset(e), e.ahead, bits)
+            }
+            _ => return Err(invalid()),
+        };
+
+        // Keep tables addressable and rows inside the visible course
+        if ahead == 0 || ahead > PLAYER_ROW || bits > MAX_STATE_BITS {
+            return Err(GameError::InvalidConfig(format!(
+                "encoder {} is out of range",
+                s
+            )));
+        }
+        Ok(encoder)
+    }
+}
+
+/// Upper bound on the size of a state space, in bits
+const MAX_STATE_BITS: f64 = 32.0;
+
+/// Checks if there's a wall at the given coordinates
+pub(crate) fn is_wall_at(game: &Game, x: i32, y: i32) -> bool {
+    if y < 0 || x < 0 || x >= COLS_COUNT as i32 || y >= ROWS_COUNT as i32 {
+        return false;
+    }
+    game.get_cell(x as usize, y as usize).is_wall()
+}
+
+/// Offset from the car to the centre of the nearest run of empty cells
+fn gap_offset(game: &Game, y: i32) -> i32 {
+    let player_x = game.player_x as i32;
+    let mut best: Option<i32> = None;
+    let mut x = 0;
+
+    while x < COLS_COUNT as i32 {
+        if is_wall_at(game, x, y) {
+            x += 1;
+            continue;
+        }
+        let start = x;
+        while x < COLS_COUNT as i32 && !is_wall_at(game, x, y) {
+            x += 1;
+        }
+        let offset = (start + x - 1) / 2 - player_x;
+        if best.is_none_or(|b| offset.abs() < b.abs()) {
+            best = Some(offset);
+        }
+    }
+
+    best.unwrap_or(0)
+}
+
+/// Counts free cells from the car towards `step` until a wall or the edge
+fn free_cells(game: &Game, y: i32, step: i32) -> usize {
+    let mut x = game.player_x as i32 + step;
+    let mut count = 0;
+    while (0..COLS_COUNT as i32).contains(&x) && !is_wall_at(game, x, y) {
+        count += 1;
+        x += step;
+    }
+    count
+}
@@ -8,6 +8,20 @@ pub enum GameError {
     Io(io::Error),
     /// Failed to load agent data
     InvalidAgentData(String),
+    /// Course code could not be decoded
+    InvalidCourseCode(String),
+    /// Daily challenge history is malformed
+    InvalidChallengeData(String),
+    /// Invalid configuration or command-line option
+    InvalidConfig(String),
+    /// Model file was written by a newer or unknown format version
+    UnsupportedVersion(u16),
+    /// Q-table doesn't have the number of states the model describes
+    ShapeMismatch { expected: usize, found: usize },
+    /// Model file contents don't match their checksum
+    ChecksumMismatch { expected: u32, found: u32 },
+    /// Model holds a NaN or infinite value, such as a Q-value or epsilon
+    NonFiniteValue(String),
 }
 
 impl fmt::Display for GameError {
@@ -15,6 +29,23 @@ impl fmt::Display for GameError {
         match self {
             GameError::Io(err) => write!(f, "I/O error: {}", err),
             GameError::InvalidAgentData(msg) => write!(f, "Invalid agent data: {}", msg),
+            GameError::InvalidCourseCode(msg) => write!(f, "Invalid course code: {}", msg),
+            GameError::InvalidChallengeData(msg) => write!(f, "Invalid challenge data: {}", msg),
+            GameError::InvalidConfig(msg) => write!(f, "Invalid configuration: {}", msg),
+            GameError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported model version: {}", version)
+            }
+            GameError::ShapeMismatch { expected, found } => write!(
+                f,
+                "Q-table shape mismatch: expected {} states, found {}",
+                expected, found
+            ),
+            GameError::ChecksumMismatch { expected, found } => write!(
+                f,
+                "Checksum mismatch: expected {:08x}, found {:08x}",
+                expected, found
+            ),
+            GameError::NonFiniteValue(what) => write!(f, "Non-finite {}", what),
         }
     }
 }
@@ -23,7 +54,14 @@ impl std::error::Error for GameError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             GameError::Io(err) => Some(err),
-            GameError::InvalidAgentData(_) => None,
+            GameError::InvalidAgentData(_)
+            | GameError::InvalidCourseCode(_)
+            | GameError::InvalidChallengeData(_)
+            | GameError::InvalidConfig(_)
+            | GameError::UnsupportedVersion(_)
+            | GameError::ShapeMismatch { .. }
+            | GameError::ChecksumMismatch { .. }
+            | GameError::NonFiniteValue(_) => None,
         }
     }
 }
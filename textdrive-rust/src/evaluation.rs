@@ -0,0 +1,196 @@
+use crate::policy::Policy;
+use crate::qlearning::Action;
+use crate::rng::splitmix64;
+use crate::vec_game::{StepOutcome, VecGame};
+
+/// Seed of the default course suite
+pub const DEFAULT_SUITE_SEED: u64 = 0x5EED_C0DE;
+/// Courses in the default suite
+pub const DEFAULT_SUITE_COURSES: usize = 100;
+/// z-score of a two-sided 95% confidence interval
+const Z_95: f64 = 1.96;
+
+/// Fixed set of seeded courses to compare policies on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvalSuite {
+    seeds: Vec<u64>,
+    max_steps: usize,
+}
+
+impl EvalSuite {
+    /// Creates a suite of `courses` courses derived from `seed`
+    ///
+    /// A course that hasn't crashed after `max_steps` steps counts as
+    /// survived.
+    pub fn new(seed: u64, courses: usize, max_steps: usize) -> Self {
+        let mut state = seed;
+        Self {
+            seeds: (0..courses).map(|_| splitmix64(&mut state)).collect(),
+            max_steps,
+        }
+    }
+
+    /// Course seeds of the suite
+    pub fn seeds(&self) -> &[u64] {
+        &self.seeds
+    }
+
+    /// Runs a policy greedily over every course of the suite
+    ///
+    /// All courses are stepped together; a course that ends keeps being
+    /// stepped, on a new course, but no longer counts.
+    pub fn evaluate<P: Policy + ?Sized>(&self, policy: &P) -> Evaluation {
+        let mut games = VecGame::with_seeds(&self.seeds);
+        let mut scores: Vec<Option<i32>> = vec![None; games.len()];
+        let mut crashes = 0;
+        let mut states = Vec::with_capacity(games.len());
+        let mut actions = Vec::with_capacity(games.len());
+        let mut outcome = StepOutcome::default();
+
+        for _ in 0..self.max_steps {
+            games.observe(policy.encoder(), &mut states);
+            actions.clear();
+            actions.extend(
+                scores
+                    .iter()
+                    .zip(&states)
+                    .map(|(score, &state)| match score {
+                        None => policy.best_action(state),
+                        Some(_) => Action::Stay,
+                    }),
+            );
+            games.step(&actions, &mut outcome);
+
+            for (score, (&crashed, &distance)) in scores
+                .iter_mut()
+                .zip(outcome.crashed.iter().zip(&outcome.distances))
+            {
+                if score.is_none() && crashed {
+                    *score = Some(distance);
+                    crashes += 1;
+                }
+            }
+            if scores.iter().all(Option::is_some) {
+                break;
+            }
+        }
+
+        let scores = (0..games.len())
+            .map(|game| scores[game].unwrap_or_else(|| games.distance(game)))
+            .collect();
+        Evaluation::new(scores, crashes)
+    }
+}
+
+impl Default for EvalSuite {
+    fn default() -> Self {
+        Self::new(DEFAULT_SUITE_SEED, DEFAULT_SUITE_COURSES, 10000)
+    }
+}
+
+/// Scores of a policy over an [`EvalSuite`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evaluation {
+    /// Distance reached on each course, ascending
+    scores: Vec<i32>,
+    crashes: usize,
+}
+
+impl Evaluation {
+    /// Creates an evaluation from the distance reached on each course and
+    /// the number of those courses that ended in a crash
+    pub fn new(mut scores: Vec<i32>, crashes: usize) -> Self {
+        scores.sort_unstable();
+        Self { scores, crashes }
+    }
+
+    /// Distances reached, ascending
+    pub fn scores(&self) -> &[i32] {
+        &self.scores
+    }
+
+    /// Number of courses evaluated
+    pub fn courses(&self) -> usize {
+        self.scores.len()
+    }
+
+    /// Mean distance
+    pub fn mean(&self) -> f64 {
+        if self.scores.is_empty() {
+            return 0.0;
+        }
+        self.scores.iter().map(|&s| f64::from(s)).sum::<f64>() / self.scores.len() as f64
+    }
+
+    /// 95% confidence interval of the mean
+    pub fn mean_interval(&self) -> (f64, f64) {
+        let n = self.scores.len() as f64;
+        let mean = self.mean();
+        if self.scores.len() < 2 {
+            return (mean, mean);
+        }
+        let variance = self
+            .scores
+            .iter()
+            .map(|&s| (f64::from(s) - mean).powi(2))
+            .sum::<f64>()
+            / (n - 1.0);
+        let margin = Z_95 * (variance / n).sqrt();
+        (mean - margin, mean + margin)
+    }
+
+    /// Median distance
+    pub fn median(&self) -> i32 {
+        self.percentile(50.0)
+    }
+
+    /// Nearest-rank percentile of the distances, `p` from 0 to 100
+    pub fn percentile(&self, p: f64) -> i32 {
+        if self.scores.is_empty() {
+            return 0;
+        }
+        let rank = (p.clamp(0.0, 100.0) / 100.0 * self.scores.len() as f64).ceil() as usize;
+        self.scores[rank.saturating_sub(1)]
+    }
+
+    /// Fraction of courses that ended in a crash
+    pub fn crash_rate(&self) -> f64 {
+        if self.scores.is_empty() {
+            return 0.0;
+        }
+        self.crashes as f64 / self.scores.len() as f64
+    }
+
+    /// 95% Wilson score interval of the crash rate
+    pub fn crash_rate_interval(&self) -> (f64, f64) {
+        if self.scores.is_empty() {
+            return (0.0, 0.0);
+        }
+        let n = self.scores.len() as f64;
+        let rate = self.crash_rate();
+        let z2 = Z_95 * Z_95;
+        let center = (rate + z2 / (2.0 * n)) / (1.0 + z2 / n);
+        let margin = Z_95 / (1.0 + z2 / n) * (rate * (1.0 - rate) / n + z2 / (4.0 * n * n)).sqrt();
+        ((center - margin).max(0.0), (center + margin).min(1.0))
+    }
+
+    /// Fraction of courses that reached at least `distance`
+    pub fn survival(&self, distance: i32) -> f64 {
+        if self.scores.is_empty() {
+            return 0.0;
+        }
+        let below = self.scores.partition_point(|&s| s < distance);
+        (self.scores.len() - below) as f64 / self.scores.len() as f64
+    }
+
+    /// Survival at `points` evenly spaced distances up to the best score
+    pub fn survival_curve(&self, points: usize) -> Vec<(i32, f64)> {
+        let best = self.scores.last().copied().unwrap_or(0);
+        (1..=points)
+            .map(|i| {
+                let distance = (i64::from(best) * i as i64 / points as i64) as i32;
+                (distance, self.survival(distance))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,410 @@
+use crate::encoder::{Encoder, StateEncoder};
+use crate::error::{GameError, Result};
+use crate::model;
+use crate::policy::Policy;
+use crate::qlearning::{best_action, Action, Agent, NUM_ACTIONS};
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use half::f16;
+use std::fmt;
+use std::fs;
+use std::io::{Read, Write};
+use std::str::FromStr;
+
+/// Marks an exported policy file
+pub const POLICY_MAGIC: &[u8; 4] = b"TDQP";
+/// Version written by [`QuantizedPolicy::write_to`]
+pub const POLICY_VERSION: u16 = 1;
+
+/// Payload is deflate-compressed
+const FLAG_COMPRESSED: u8 = 1;
+/// Actions packed into each byte of an argmax table
+const ACTIONS_PER_BYTE: usize = 4;
+
+/// How Q-values are stored in an exported policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precision {
+    /// Half-precision floats, 6 bytes per state
+    F16,
+    /// Bytes scaled by one f32 per state, 7 bytes per state
+    I8,
+    /// Only the greedy action, 2 bits per state
+    Argmax,
+}
+
+impl Precision {
+    /// Short name used on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            Precision::F16 => "f16",
+            Precision::I8 => "i8",
+            Precision::Argmax => "argmax",
+        }
+    }
+
+    fn code(self) -> u8 {
+        match self {
+            Precision::F16 => 0,
+            Precision::I8 => 1,
+            Precision::Argmax => 2,
+        }
+    }
+
+    fn from_code(code: u8) -> Option<Self> {
+        [Precision::F16, Precision::I8, Precision::Argmax]
+            .into_iter()
+            .find(|precision| precision.code() == code)
+    }
+
+    /// Payload size of a table with `states` states, if it fits in memory
+    fn payload_len(self, states: usize) -> Option<usize> {
+        match self {
+            Precision::F16 => states.checked_mul(NUM_ACTIONS * 2),
+            Precision::I8 => states.checked_mul(4 + NUM_ACTIONS),
+            Precision::Argmax => Some(states.div_ceil(ACTIONS_PER_BYTE)),
+        }
+    }
+}
+
+impl fmt::Display for Precision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Precision {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        [Precision::F16, Precision::I8, Precision::Argmax]
+            .into_iter()
+            .find(|precision| precision.name() == s)
+            .ok_or_else(|| GameError::InvalidConfig(format!("unknown precision: {}", s)))
+    }
+}
+
+/// Quantized Q-values
+#[derive(Debug, Clone, PartialEq)]
+enum Values {
+    F16(Vec<f16>),
+    I8 { scales: Vec<f32>, values: Vec<i8> },
+    Argmax(Vec<u8>),
+}
+
+/// Compact read-only policy exported from a trained agent
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuantizedPolicy {
+    encoder: Encoder,
+    states: usize,
+    values: Values,
+}
+
+/// How much quantization changed a policy
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuantizationReport {
+    pub states: usize,
+    /// States whose greedy action differs from the original agent's
+    pub changed_actions: usize,
+    /// Largest absolute error of a Q-value (not meaningful for argmax)
+    pub max_error: f64,
+}
+
+impl QuantizationReport {
+    /// Fraction of states whose greedy action changed
+    pub fn changed_fraction(&self) -> f64 {
+        if self.states == 0 {
+            0.0
+        } else {
+            self.changed_actions as f64 / self.states as f64
+        }
+    }
+}
+
+impl QuantizedPolicy {
+    /// Quantizes an agent's Q-values (averaged with Double Q-learning)
+    pub fn quantize(agent: &Agent, precision: Precision) -> Self {
+        let states = agent.q.len();
+        let values = match precision {
+            Precision::F16 => Values::F16(
+                (0..states)
+                    .flat_map(|state| agent.q_values(state))
+                    .map(f16::from_f64)
+                    .collect(),
+            ),
+            Precision::I8 => {
+                let mut scales = Vec::with_capacity(states);
+                let mut values = Vec::with_capacity(states * NUM_ACTIONS);
+                for state in 0..states {
+                    let row = agent.q_values(state);
+                    let largest = row.iter().fold(0.0f64, |max, q| max.max(q.abs()));
+                    let scale = (largest / i8::MAX as f64) as f32;
+                    scales.push(scale);
+                    values.extend(row.iter().map(|&q| {
+                        if scale == 0.0 {
+                            0
+                        } else {
+                            (q / scale as f64).round() as i8
+                        }
+                    }));
+                }
+                Values::I8 { scales, values }
+            }
+            Precision::Argmax => {
+                let mut packed = vec![0u8; states.div_ceil(ACTIONS_PER_BYTE)];
+                for state in 0..states {
+                    let action = best_action(&agent.q_values(state)) as u8;
+                    packed[state / ACTIONS_PER_BYTE] |= action << (2 * (state % ACTIONS_PER_BYTE));
+                }
+                Values::Argmax(packed)
+            }
+        };
+
+        Self {
+            encoder: agent.encoder.clone(),
+            states,
+            values,
+        }
+    }
+
+    /// Storage precision
+    pub fn precision(&self) -> Precision {
+        match self.values {
+            Values::F16(_) => Precision::F16,
+            Values::I8 { .. } => Precision::I8,
+            Values::Argmax(_) => Precision::Argmax,
+        }
+    }
+
+    /// Number of states the policy covers
+    pub fn states(&self) -> usize {
+        self.states
+    }
+
+    /// Compares the policy with the agent it was quantized from
+    pub fn compare(&self, agent: &Agent) -> QuantizationReport {
+        let mut report = QuantizationReport {
+            states: self.states,
+            changed_actions: 0,
+            max_error: 0.0,
+        };
+        for state in 0..self.states {
+            let original = agent.q_values(state);
+            if best_action(&original) != self.best_action(state) {
+                report.changed_actions += 1;
+            }
+            if self.precision() != Precision::Argmax {
+                for (q, quantized) in original.iter().zip(self.q_values(state)) {
+                    report.max_error = report.max_error.max((q - quantized).abs());
+                }
+            }
+        }
+        report
+    }
+
+    /// Saves the policy to a file
+    ///
+    /// The file is replaced in one step, like a model's.
+    pub fn save(&self, filename: &str, compress: bool) -> Result<()> {
+        model::replace_file(filename, |writer| self.write_to(writer, compress))
+    }
+
+    /// Writes the policy to any writer
+    ///
+    /// The layout is the magic bytes, version, flags, precision, encoder id
+    /// and state count, then the payload (deflated if `compress`) and a
+    /// CRC-32 of everything before it.
+    pub fn write_to(&self, mut writer: impl Write, compress: bool) -> Result<()> {
+        let payload = self.payload();
+        let payload = if compress {
+            let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+            encoder.write_all(&payload)?;
+            encoder.finish()?
+        } else {
+            payload
+        };
+
+        let id = self.encoder.id();
+        let mut bytes = Vec::with_capacity(payload.len() + 32);
+        bytes.extend_from_slice(POLICY_MAGIC);
+        bytes.extend_from_slice(&POLICY_VERSION.to_le_bytes());
+        bytes.push(if compress { FLAG_COMPRESSED } else { 0 });
+        bytes.push(self.precision().code());
+        bytes.extend_from_slice(&(id.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(id.as_bytes());
+        bytes.extend_from_slice(&(self.states as u64).to_le_bytes());
+        bytes.extend_from_slice(&payload);
+        let checksum = crc32fast::hash(&bytes);
+        bytes.extend_from_slice(&checksum.to_le_bytes());
+
+        writer.write_all(&bytes)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Loads a policy from a file
+    pub fn load(filename: &str) -> Result<Self> {
+        Self::from_bytes(&fs::read(filename)?)
+    }
+
+    /// Reads a policy from any reader
+    pub fn read_from(mut reader: impl Read) -> Result<Self> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        Self::from_bytes(&bytes)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let truncated = || GameError::InvalidAgentData("file is truncated".to_string());
+        if !bytes.starts_with(POLICY_MAGIC) {
+            return Err(GameError::InvalidAgentData(
+                "not an exported policy".to_string(),
+            ));
+        }
+        // Magic, version, flags, precision, id length and checksum
+        if bytes.len() < POLICY_MAGIC.len() + 2 + 1 + 1 + 4 + 4 {
+            return Err(truncated());
+        }
+
+        let (body, trailer) = bytes.split_at(bytes.len() - 4);
+        let expected = u32::from_le_bytes(trailer.try_into().unwrap());
+        let found = crc32fast::hash(body);
+        if expected != found {
+            return Err(GameError::ChecksumMismatch { expected, found });
+        }
+
+        let version = u16::from_le_bytes([body[4], body[5]]);
+        if version != POLICY_VERSION {
+            return Err(GameError::UnsupportedVersion(version));
+        }
+        let flags = body[6];
+        let precision = Precision::from_code(body[7]).ok_or_else(|| {
+            GameError::InvalidAgentData(format!("unknown precision code {}", body[7]))
+        })?;
+
+        let rest = &body[8..];
+        let id_len = u32::from_le_bytes(rest[..4].try_into().unwrap()) as usize;
+        let rest = &rest[4..];
+        if rest.len() < id_len + 8 {
+            return Err(truncated());
+        }
+        let id = String::from_utf8_lossy(&rest[..id_len]);
+        let encoder: Encoder = id
+            .parse()
+            .map_err(|_| GameError::InvalidAgentData(format!("unknown encoder: {}", id)))?;
+        let states = u64::from_le_bytes(rest[id_len..id_len + 8].try_into().unwrap()) as usize;
+        if states != encoder.state_count() {
+            return Err(GameError::ShapeMismatch {
+                expected: encoder.state_count(),
+                found: states,
+            });
+        }
+
+        let expected_len = precision.payload_len(states).ok_or_else(|| {
+            GameError::InvalidAgentData(format!("{} states are too many to load", states))
+        })?;
+        let payload = &rest[id_len + 8..];
+        let payload = if flags & FLAG_COMPRESSED != 0 {
+            // One byte past the table is enough to tell it's too long
+            let mut inflated = Vec::new();
+            DeflateDecoder::new(payload)
+                .take(expected_len as u64 + 1)
+                .read_to_end(&mut inflated)?;
+            inflated
+        } else {
+            payload.to_vec()
+        };
+        if payload.len() != expected_len {
+            return Err(GameError::ShapeMismatch {
+                expected: states,
+                found: states.saturating_mul(payload.len()) / expected_len.max(1),
+            });
+        }
+
+        let values = match precision {
+            Precision::F16 => Values::F16(
+                payload
+                    .chunks_exact(2)
+                    .map(|bits| f16::from_le_bytes([bits[0], bits[1]]))
+                    .collect(),
+            ),
+            Precision::I8 => {
+                let (scales, values) = payload.split_at(states * 4);
+                Values::I8 {
+                    scales: scales
+                        .chunks_exact(4)
+                        .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+                        .collect(),
+                    values: values.iter().map(|&b| b as i8).collect(),
+                }
+            }
+            Precision::Argmax => Values::Argmax(payload),
+        };
+
+        Ok(Self {
+            encoder,
+            states,
+            values,
+        })
+    }
+
+    fn payload(&self) -> Vec<u8> {
+        match self.values {
+            Values::F16(ref values) => values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+            Values::I8 {
+                ref scales,
+                ref values,
+            } => scales
+                .iter()
+                .flat_map(|s| s.to_le_bytes())
+                .chain(values.iter().map(|&v| v as u8))
+                .collect(),
+            Values::Argmax(ref packed) => packed.clone(),
+        }
+    }
+
+    fn argmax(packed: &[u8], state: usize) -> Action {
+        let bits = packed[state / ACTIONS_PER_BYTE] >> (2 * (state % ACTIONS_PER_BYTE));
+        Action::from_usize((bits & 0b11) as usize).unwrap_or(Action::Stay)
+    }
+}
+
+impl Policy for QuantizedPolicy {
+    fn encoder(&self) -> &Encoder {
+        &self.encoder
+    }
+
+    /// Dequantized Q-values; argmax tables give 1 for the greedy action and
+    /// 0 for the others
+    fn q_values(&self, state: usize) -> [f64; NUM_ACTIONS] {
+        assert!(state < self.states, "state {} out of range", state);
+        let mut row = [0.0; NUM_ACTIONS];
+        match self.values {
+            Values::F16(ref values) => {
+                for (q, v) in row.iter_mut().zip(&values[state * NUM_ACTIONS..]) {
+                    *q = v.to_f64();
+                }
+            }
+            Values::I8 {
+                ref scales,
+                ref values,
+            } => {
+                let scale = scales[state] as f64;
+                for (q, &v) in row.iter_mut().zip(&values[state * NUM_ACTIONS..]) {
+                    *q = v as f64 * scale;
+                }
+            }
+            Values::Argmax(ref packed) => {
+                row[Self::argmax(packed, state) as usize] = 1.0;
+            }
+        }
+        row
+    }
+
+    fn best_action(&self, state: usize) -> Action {
+        match self.values {
+            Values::Argmax(ref packed) => Self::argmax(packed, state),
+            _ => best_action(&self.q_values(state)),
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::rng::SimRng;
 use rand::Rng;
 
 pub const COLS_COUNT: usize = 9;
@@ -67,6 +68,26 @@ const COURSE_PATTERNS: [&str; 12] = [
     "##   ####",
 ];
 
+/// Wall bit per column of each pattern
+const PATTERN_MASKS: [u16; COURSE_PATTERNS.len()] = pattern_masks();
+
+const fn pattern_masks() -> [u16; COURSE_PATTERNS.len()] {
+    let mut masks = [0; COURSE_PATTERNS.len()];
+    let mut pattern = 0;
+    while pattern < COURSE_PATTERNS.len() {
+        let cells = COURSE_PATTERNS[pattern].as_bytes();
+        let mut x = 0;
+        while x < COLS_COUNT {
+            if cells[x] == b'#' {
+                masks[pattern] |= 1 << x;
+            }
+            x += 1;
+        }
+        pattern += 1;
+    }
+    masks
+}
+
 /// Represents the game state
 #[derive(Clone)]
 pub struct Game {
@@ -75,30 +96,70 @@ pub struct Game {
     pattern: usize,
     row_count: usize,
     pub game_over: bool,
-    rows: [[Cell; COLS_COUNT]; ROWS_COUNT],
+    /// Ring buffer of wall masks; the top row is at `top`
+    rows: [u16; ROWS_COUNT],
+    top: usize,
+    seed: u64,
+    rng: SimRng,
 }
 
 impl Game {
-    /// Creates a new game instance
+    /// Creates a new game instance on a random course
     pub fn new() -> Self {
+        Self::with_seed(rand::rng().random())
+    }
+
+    /// Creates a new game instance whose course is fully determined by `seed`
+    pub fn with_seed(seed: u64) -> Self {
         Self {
             player_x: COLS_COUNT / 2,
             distance: 0,
             pattern: 0,
             row_count: 0,
             game_over: false,
-            rows: [[Cell::Empty; COLS_COUNT]; ROWS_COUNT],
+            rows: [0; ROWS_COUNT],
+            top: 0,
+            seed,
+            rng: SimRng::new(seed),
         }
     }
 
-    /// Resets the game to initial state
+    /// Resets the game to initial state on a new random course
     pub fn init(&mut self) {
         *self = Self::new();
     }
 
+    /// Resets the game to the start of the current course
+    pub fn restart(&mut self) {
+        *self = Self::with_seed(self.seed);
+    }
+
+    /// Gets the seed the course was generated from
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
     /// Gets the cell at the specified position
+    ///
+    /// Panics on a position outside the board.
     pub fn get_cell(&self, x: usize, y: usize) -> Cell {
-        self.rows[y][x]
+        assert!(
+            x < COLS_COUNT && y < ROWS_COUNT,
+            "cell ({}, {}) is off the board",
+            x,
+            y
+        );
+        if self.row_bits(y) >> x & 1 == 1 {
+            Cell::Wall
+        } else {
+            Cell::Empty
+        }
+    }
+
+    /// Gets the walls of a row as a bitmask, bit `x` for column `x`
+    pub fn row_bits(&self, y: usize) -> u16 {
+        assert!(y < ROWS_COUNT, "row {} is off the board", y);
+        self.rows[(self.top + y) % ROWS_COUNT]
     }
 
     /// Gets the row count (visible rows)
@@ -113,22 +174,21 @@ impl Game {
         self.update_progress();
     }
 
+    /// The bottom row drops out of the ring and becomes the new top
     fn shift_rows_down(&mut self) {
-        self.rows.copy_within(0..ROWS_COUNT - 1, 1);
+        self.top = (self.top + ROWS_COUNT - 1) % ROWS_COUNT;
     }
 
     fn update_pattern(&mut self) {
-        let mut rng = rand::rng();
-        let change = rng.random_range(PATTERN_CHANGE_MIN..=PATTERN_CHANGE_MAX);
+        let change = self
+            .rng
+            .random_range(PATTERN_CHANGE_MIN..=PATTERN_CHANGE_MAX);
         let pattern_count = COURSE_PATTERNS.len() as i32;
         self.pattern = ((self.pattern as i32 + change + pattern_count) % pattern_count) as usize;
     }
 
     fn apply_pattern(&mut self) {
-        let pattern_str = COURSE_PATTERNS[self.pattern];
-        for (i, c) in pattern_str.chars().enumerate() {
-            self.rows[0][i] = Cell::from_char(c);
-        }
+        self.rows[self.top] = PATTERN_MASKS[self.pattern];
     }
 
     fn update_progress(&mut self) {
@@ -143,7 +203,7 @@ impl Game {
         if PLAYER_ROW >= self.row_count {
             return false;
         }
-        self.rows[PLAYER_ROW][self.player_x].is_wall()
+        self.row_bits(PLAYER_ROW) >> self.player_x & 1 == 1
     }
 
     /// Moves the player in the specified direction
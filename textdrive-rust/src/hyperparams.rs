@@ -0,0 +1,252 @@
+use crate::error::GameError;
+use crate::game::Game;
+use std::f64::consts::PI;
+use std::fmt;
+use std::str::FromStr;
+
+pub const DEFAULT_ALPHA: f64 = 0.2;
+pub const DEFAULT_GAMMA: f64 = 0.95;
+pub const DEFAULT_EPSILON_DECAY: f64 = 0.9995;
+pub const DEFAULT_COLLISION_REWARD: f64 = -100.0;
+pub const DEFAULT_SURVIVAL_REWARD: f64 = 1.0;
+
+/// What a schedule counts when it decays
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecayUnit {
+    Episode,
+    Step,
+}
+
+/// Shape of a decaying value over time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Decay {
+    /// Stays at the start value
+    Constant,
+    /// Multiplies by `rate` every unit
+    Exponential { rate: f64 },
+    /// Moves linearly from the start value to `end` over `over` units
+    Linear { end: f64, over: u64 },
+    /// Multiplies by `factor` once every `every` units
+    Step { factor: f64, every: u64 },
+    /// Follows half a cosine wave from the start value to `end` over `over` units
+    Cosine { end: f64, over: u64 },
+}
+
+/// A value that decays over episodes or steps, never dropping below `min`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Schedule {
+    pub start: f64,
+    pub min: f64,
+    pub decay: Decay,
+    pub unit: DecayUnit,
+}
+
+impl Schedule {
+    /// Creates a schedule that always returns `value`
+    pub fn constant(value: f64) -> Self {
+        Self {
+            start: value,
+            min: 0.0,
+            decay: Decay::Constant,
+            unit: DecayUnit::Episode,
+        }
+    }
+
+    /// Creates a per-episode exponential schedule
+    pub fn exponential(start: f64, rate: f64) -> Self {
+        Self {
+            start,
+            min: 0.0,
+            decay: Decay::Exponential { rate },
+            unit: DecayUnit::Episode,
+        }
+    }
+
+    /// Sets the floor the value never drops below
+    pub fn with_min(mut self, min: f64) -> Self {
+        self.min = min;
+        self
+    }
+
+    /// Sets what the schedule counts
+    pub fn per(mut self, unit: DecayUnit) -> Self {
+        self.unit = unit;
+        self
+    }
+
+    /// Gets the value after `t` units have elapsed
+    pub fn value(&self, t: u64) -> f64 {
+        let progress = |over: u64| {
+            if over == 0 {
+                1.0
+            } else {
+                (t as f64 / over as f64).min(1.0)
+            }
+        };
+
+        let value = match self.decay {
+            Decay::Constant => self.start,
+            Decay::Exponential { rate } => self.start * rate.powf(t as f64),
+            Decay::Linear { end, over } => self.start + (end - self.start) * progress(over),
+            Decay::Step { factor, every } => {
+                let steps = t.checked_div(every).unwrap_or(0);
+                self.start * factor.powf(steps as f64)
+            }
+            Decay::Cosine { end, over } => {
+                end + (self.start - end) * 0.5 * (1.0 + (PI * progress(over)).cos())
+            }
+        };
+        value.max(self.min)
+    }
+
+    /// Gets the value at the given point of training
+    pub fn value_at(&self, clock: ScheduleClock) -> f64 {
+        self.value(clock.get(self.unit))
+    }
+}
+
+impl DecayUnit {
+    /// Short name used in model files
+    pub fn name(self) -> &'static str {
+        match self {
+            DecayUnit::Episode => "episode",
+            DecayUnit::Step => "step",
+        }
+    }
+}
+
+impl fmt::Display for Schedule {
+    /// Formats as e.g. `exponential:0.9995 start=1 min=0.01 per=episode`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.decay {
+            Decay::Constant => write!(f, "constant")?,
+            Decay::Exponential { rate } => write!(f, "exponential:{}", rate)?,
+            Decay::Linear { end, over } => write!(f, "linear:{}:{}", end, over)?,
+            Decay::Step { factor, every } => write!(f, "step:{}:{}", factor, every)?,
+            Decay::Cosine { end, over } => write!(f, "cosine:{}:{}", end, over)?,
+        }
+        write!(
+            f,
+            " start={} min={} per={}",
+            self.start,
+            self.min,
+            self.unit.name()
+        )
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = GameError;
+
+    /// Parses the format written by `Display`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || GameError::InvalidConfig(format!("invalid schedule: {}", s));
+        let mut words = s.split_whitespace();
+        let decay = words.next().ok_or_else(invalid)?;
+        let mut args = decay.split(':');
+        let kind = args.next().unwrap_or_default();
+        let mut values = args.map(str::to_string);
+        let mut next = || values.next().ok_or_else(invalid);
+        let decay = match kind {
+            "constant" => Decay::Constant,
+            "exponential" => Decay::Exponential {
+                rate: parse_field(&next()?, s)?,
+            },
+            "linear" => Decay::Linear {
+                end: parse_field(&next()?, s)?,
+                over: parse_field(&next()?, s)?,
+            },
+            "step" => Decay::Step {
+                factor: parse_field(&next()?, s)?,
+                every: parse_field(&next()?, s)?,
+            },
+            "cosine" => Decay::Cosine {
+                end: parse_field(&next()?, s)?,
+                over: parse_field(&next()?, s)?,
+            },
+            _ => return Err(invalid()),
+        };
+
+        let mut schedule = Schedule {
+            start: 0.0,
+            min: 0.0,
+            decay,
+            unit: DecayUnit::Episode,
+        };
+        for word in words {
+            let (key, value) = word.split_once('=').ok_or_else(invalid)?;
+            match key {
+                "start" => schedule.start = parse_field(value, s)?,
+                "min" => schedule.min = parse_field(value, s)?,
+                "per" => {
+                    schedule.unit = match value {
+                        "episode" => DecayUnit::Episode,
+                        "step" => DecayUnit::Step,
+                        _ => return Err(invalid()),
+                    }
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(schedule)
+    }
+}
+
+fn parse_field<T: FromStr>(value: &str, schedule: &str) -> Result<T, GameError> {
+    value
+        .parse()
+        .map_err(|_| GameError::InvalidConfig(format!("invalid schedule: {}", schedule)))
+}
+
+/// Counts elapsed episodes and steps for schedules
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScheduleClock {
+    pub episodes: u64,
+    pub steps: u64,
+}
+
+impl ScheduleClock {
+    /// Gets the elapsed count for the given unit
+    pub fn get(&self, unit: DecayUnit) -> u64 {
+        match unit {
+            DecayUnit::Episode => self.episodes,
+            DecayUnit::Step => self.steps,
+        }
+    }
+}
+
+/// Tunable parameters of the Q-learning agent
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hyperparams {
+    /// Learning rate (α)
+    pub alpha: Schedule,
+    /// Discount factor (γ)
+    pub gamma: f64,
+    /// Exploration rate (ε)
+    pub epsilon: Schedule,
+    pub collision_reward: f64,
+    pub survival_reward: f64,
+}
+
+impl Hyperparams {
+    /// Calculates the reward for the current game state
+    pub fn reward(&self, game: &Game) -> f64 {
+        if game.has_collision() {
+            self.collision_reward
+        } else {
+            self.survival_reward
+        }
+    }
+}
+
+impl Default for Hyperparams {
+    fn default() -> Self {
+        Self {
+            alpha: Schedule::constant(DEFAULT_ALPHA),
+            gamma: DEFAULT_GAMMA,
+            epsilon: Schedule::exponential(1.0, DEFAULT_EPSILON_DECAY),
+            collision_reward: DEFAULT_COLLISION_REWARD,
+            survival_reward: DEFAULT_SURVIVAL_REWARD,
+        }
+    }
+}
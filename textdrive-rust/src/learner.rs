@@ -0,0 +1,163 @@
+use crate::error::GameError;
+use crate::qlearning::{best_action, update_q, Action, Agent, NUM_ACTIONS};
+use crate::storage::QTable;
+use rand::Rng;
+use std::fmt;
+use std::str::FromStr;
+
+/// One step of experience
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transition {
+    pub state: usize,
+    pub action: Action,
+    pub reward: f64,
+    pub next_state: usize,
+    /// Action the behaviour policy picked in the next state
+    pub next_action: Action,
+}
+
+/// Temporal-difference update rule used for training
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Learner {
+    /// Off-policy one-step Q-learning
+    #[default]
+    QLearning,
+    /// On-policy SARSA, bootstrapping from the action actually taken next
+    Sarsa,
+    /// SARSA using the expected value under the ε-greedy policy
+    ExpectedSarsa,
+    /// Double Q-learning with two tables to reduce overestimation
+    DoubleQ,
+}
+
+impl Learner {
+    /// All available learners
+    pub fn all() -> [Learner; 4] {
+        [
+            Learner::QLearning,
+            Learner::Sarsa,
+            Learner::ExpectedSarsa,
+            Learner::DoubleQ,
+        ]
+    }
+
+    /// Short name used on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            Learner::QLearning => "q-learning",
+            Learner::Sarsa => "sarsa",
+            Learner::ExpectedSarsa => "expected-sarsa",
+            Learner::DoubleQ => "double-q",
+        }
+    }
+
+    /// Prepares an agent for training with this learner
+    ///
+    /// Double Q-learning needs a second table, which starts as a copy of the
+    /// first so a trained agent can be fine-tuned.
+    pub fn prepare(self, agent: &mut Agent) {
+        if self == Learner::DoubleQ && agent.q2.is_none() {
+            agent.q2 = Some(agent.q.clone());
+        }
+    }
+
+    /// Updates the agent's Q-values from one transition
+    pub fn update(self, agent: &mut Agent, t: &Transition) {
+        match self {
+            Learner::QLearning => update_q(agent, t.state, t.action, t.reward, t.next_state),
+            Learner::Sarsa => {
+                let next_q = agent.q[t.next_state][t.next_action as usize];
+                apply_target(agent, t, next_q);
+            }
+            Learner::ExpectedSarsa => {
+                let next_q = expected_q(&agent.q[t.next_state], agent.epsilon);
+                apply_target(agent, t, next_q);
+            }
+            Learner::DoubleQ => update_double_q(agent, t),
+        }
+    }
+
+    /// Value this learner bootstraps from in the next state
+    ///
+    /// Double Q-learning bootstraps from the averaged table here; its
+    /// decoupled two-table target only exists for one-step updates.
+    pub fn bootstrap(self, agent: &Agent, next_state: usize, next_action: Action) -> f64 {
+        match self {
+            Learner::QLearning | Learner::DoubleQ => agent
+                .q_values(next_state)
+                .into_iter()
+                .fold(f64::NEG_INFINITY, f64::max),
+            Learner::Sarsa => agent.q[next_state][next_action as usize],
+            Learner::ExpectedSarsa => expected_q(&agent.q[next_state], agent.epsilon),
+        }
+    }
+}
+
+impl fmt::Display for Learner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Learner {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Learner::all()
+            .into_iter()
+            .find(|learner| learner.name() == s)
+            .ok_or_else(|| GameError::InvalidConfig(format!("unknown learner: {}", s)))
+    }
+}
+
+/// Expected Q-value of a state under an ε-greedy policy
+pub fn expected_q(q_values: &[f64; NUM_ACTIONS], epsilon: f64) -> f64 {
+    let greedy = best_action(q_values) as usize;
+    let explore = epsilon / NUM_ACTIONS as f64;
+    q_values
+        .iter()
+        .enumerate()
+        .map(|(action, &q)| {
+            let probability = if action == greedy {
+                1.0 - epsilon + explore
+            } else {
+                explore
+            };
+            probability * q
+        })
+        .sum()
+}
+
+/// Moves a Q-value towards a target by the agent's current learning rate
+pub fn update_toward(agent: &mut Agent, state: usize, action: Action, target: f64) {
+    let current_q = &mut agent.q[state][action as usize];
+    *current_q += agent.alpha * (target - *current_q);
+}
+
+fn apply_target(agent: &mut Agent, t: &Transition, next_q: f64) {
+    let target = t.reward + agent.params.gamma * next_q;
+    update_toward(agent, t.state, t.action, target);
+}
+
+fn update_double_q(agent: &mut Agent, t: &Transition) {
+    let coin = agent.rng.random::<bool>();
+    let alpha = agent.alpha;
+    let gamma = agent.params.gamma;
+    let (kind, state_count) = (agent.q.kind(), agent.q.len());
+    let q2 = agent
+        .q2
+        .get_or_insert_with(|| QTable::new(kind, state_count));
+
+    // Pick the table to update at random; the other one evaluates the action
+    // the updated table considers best
+    let (update, evaluate) = if coin {
+        (&mut agent.q, &*q2)
+    } else {
+        (q2, &agent.q)
+    };
+
+    let best = best_action(&update[t.next_state]) as usize;
+    let target = t.reward + gamma * evaluate[t.next_state][best];
+    let current_q = &mut update[t.state][t.action as usize];
+    *current_q += alpha * (target - *current_q);
+}
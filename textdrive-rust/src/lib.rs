@@ -1,3 +1,28 @@
+pub mod challenge;
+pub mod config;
+pub mod dashboard;
+pub mod encoder;
 pub mod error;
+pub mod evaluation;
+pub mod export;
 pub mod game;
+pub mod hyperparams;
+pub mod learner;
+pub mod merge;
+pub mod metrics;
+pub mod model;
+pub mod parallel;
+pub mod policy;
+pub mod postmortem;
 pub mod qlearning;
+pub mod race;
+pub mod render;
+pub mod rewind;
+pub mod rng;
+pub mod stopping;
+pub mod storage;
+pub mod sweep;
+pub mod tensorboard;
+pub mod traces;
+pub mod training;
+pub mod vec_game;
@@ -0,0 +1,207 @@
+use crate::encoder::StateEncoder;
+use crate::error::{GameError, Result};
+use crate::qlearning::{best_action, Action, Agent, NUM_ACTIONS};
+use crate::storage::StorageKind;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// How [`merge`] combines the rows of several Q-tables
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergeMode {
+    /// Weighted average of every model's Q-values
+    #[default]
+    Average,
+    /// Row of the model that is most sure of its greedy action
+    MaxConfidence,
+}
+
+impl MergeMode {
+    /// Short name used on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            MergeMode::Average => "average",
+            MergeMode::MaxConfidence => "max-confidence",
+        }
+    }
+}
+
+impl fmt::Display for MergeMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for MergeMode {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        [MergeMode::Average, MergeMode::MaxConfidence]
+            .into_iter()
+            .find(|mode| mode.name() == s)
+            .ok_or_else(|| GameError::InvalidConfig(format!("unknown merge mode: {}", s)))
+    }
+}
+
+/// A state where two models pick different greedy actions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ActionDiff {
+    pub state: usize,
+    pub left: Action,
+    pub right: Action,
+    /// Combined visits of both models, if both tracked them
+    pub visits: Option<u64>,
+}
+
+/// Lists the states whose greedy action differs between two models
+///
+/// Sorted by visits (most visited first) when both models have them,
+/// otherwise by state.
+pub fn diff(left: &Agent, right: &Agent) -> Result<Vec<ActionDiff>> {
+    check_compatible(&[left, right])?;
+
+    let mut diffs: Vec<ActionDiff> = union_states(&[left, right])
+        .into_iter()
+        .filter_map(|state| {
+            let l = best_action(&left.q_values(state));
+            let r = best_action(&right.q_values(state));
+            (l != r).then(|| ActionDiff {
+                state,
+                left: l,
+                right: r,
+                visits: left
+                    .visit_count(state)
+                    .zip(right.visit_count(state))
+                    .map(|(a, b)| a + b),
+            })
+        })
+        .collect();
+
+    diffs.sort_by_key(|diff| std::cmp::Reverse(diff.visits.unwrap_or(0)));
+    Ok(diffs)
+}
+
+/// Combines Q-tables from several training runs of the same encoder
+///
+/// Each model comes with a weight. When every model tracked visits, a
+/// state's weight is also scaled by how often that model visited it, so
+/// runs that explored a state more have more say there. Max-confidence
+/// takes, per state, the row with the largest gap between its best and
+/// second-best Q-value. Weights must be finite, non-negative and not all
+/// zero.
+pub fn merge(models: &[(&Agent, f64)], mode: MergeMode) -> Result<Agent> {
+    let agents: Vec<&Agent> = models.iter().map(|&(agent, _)| agent).collect();
+    check_compatible(&agents)?;
+    if !valid_weights(models.iter().map(|&(_, weight)| weight)) {
+        return Err(GameError::InvalidConfig(
+            "merge weights must be non-negative and not all zero".to_string(),
+        ));
+    }
+    let first = agents[0];
+
+    let storage = if agents.iter().all(|a| a.q.kind() == StorageKind::Sparse) {
+        StorageKind::Sparse
+    } else {
+        StorageKind::Dense
+    };
+    let mut merged = Agent::with_storage(first.encoder.clone(), first.params, storage);
+    let use_visits = agents.iter().all(|a| a.visits.is_some());
+
+    for state in union_states(&agents) {
+        merged.q[state] = match mode {
+            MergeMode::Average => {
+                let weight = |&(agent, weight): &(&Agent, f64)| {
+                    let visits = if use_visits {
+                        agent.visit_count(state).unwrap_or(0) as f64
+                    } else {
+                        1.0
+                    };
+                    weight * visits
+                };
+                let total: f64 = models.iter().map(weight).sum();
+                let mut row = [0.0; NUM_ACTIONS];
+                for model in models {
+                    // Unvisited everywhere: fall back to the model weights
+                    let share = if total > 0.0 {
+                        weight(model) / total
+                    } else {
+                        model.1 / models.iter().map(|m| m.1).sum::<f64>()
+                    };
+                    for (value, q) in row.iter_mut().zip(model.0.q_values(state)) {
+                        *value += share * q;
+                    }
+                }
+                row
+            }
+            MergeMode::MaxConfidence => agents
+                .iter()
+                .map(|agent| agent.q_values(state))
+                .max_by(|a, b| confidence(a).total_cmp(&confidence(b)))
+                .unwrap_or([0.0; NUM_ACTIONS]),
+        };
+    }
+
+    merged.epsilon = agents
+        .iter()
+        .map(|a| a.epsilon)
+        .fold(f64::INFINITY, f64::min);
+    merged.best_score = agents.iter().map(|a| a.best_score).max().unwrap_or(0);
+    merged.episodes = agents.iter().map(|a| a.episodes).sum();
+    merged.clock.episodes = merged.episodes;
+    if use_visits {
+        let mut visits = HashMap::new();
+        for agent in &agents {
+            for (&state, &count) in agent.visits.iter().flatten() {
+                *visits.entry(state).or_insert(0) += count;
+            }
+        }
+        merged.visits = Some(visits);
+    }
+    Ok(merged)
+}
+
+/// Gap between the best and second-best Q-value
+fn confidence(row: &[f64; NUM_ACTIONS]) -> f64 {
+    let mut sorted = *row;
+    sorted.sort_by(|a, b| b.total_cmp(a));
+    sorted[0] - sorted[1]
+}
+
+fn check_compatible(agents: &[&Agent]) -> Result<()> {
+    let Some(first) = agents.first() else {
+        return Err(GameError::InvalidConfig("no models given".to_string()));
+    };
+    for agent in agents {
+        if agent.encoder != first.encoder {
+            return Err(GameError::InvalidConfig(format!(
+                "models use different encoders: {} and {}",
+                first.encoder.id(),
+                agent.encoder.id()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// States stored by any of the agents, in ascending order
+fn union_states(agents: &[&Agent]) -> Vec<usize> {
+    if agents.iter().any(|a| a.q.kind() == StorageKind::Dense) {
+        return (0..agents[0].q.len()).collect();
+    }
+    let mut states: Vec<usize> = agents.iter().flat_map(|a| a.stored_states()).collect();
+    states.sort_unstable();
+    states.dedup();
+    states
+}
+
+/// Whether weights are finite, non-negative and not all zero
+pub fn valid_weights(weights: impl IntoIterator<Item = f64>) -> bool {
+    let mut any_positive = false;
+    for weight in weights {
+        if !weight.is_finite() || weight < 0.0 {
+            return false;
+        }
+        any_positive |= weight > 0.0;
+    }
+    any_positive
+}
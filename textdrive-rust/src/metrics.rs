@@ -0,0 +1,205 @@
+use crate::error::{GameError, Result};
+use crate::evaluation::Evaluation;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// What happened in one training episode
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EpisodeMetrics {
+    pub episode: u64,
+    /// Steps until the crash or the step cap
+    pub steps: usize,
+    /// Sum of the rewards
+    pub total_reward: f64,
+    pub distance: i32,
+    pub epsilon: f64,
+    pub alpha: f64,
+    /// Mean absolute one-step TD error of the episode's updates
+    pub mean_abs_td_error: f64,
+    /// Distinct states visited since training started
+    pub states_visited: usize,
+    /// Greedy evaluation run after this episode, if any
+    pub evaluation: Option<EvalMetrics>,
+}
+
+/// Summary of an evaluation for the metrics log
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EvalMetrics {
+    pub mean: f64,
+    pub median: i32,
+    pub crash_rate: f64,
+}
+
+impl From<&Evaluation> for EvalMetrics {
+    fn from(evaluation: &Evaluation) -> Self {
+        Self {
+            mean: evaluation.mean(),
+            median: evaluation.median(),
+            crash_rate: evaluation.crash_rate(),
+        }
+    }
+}
+
+impl EpisodeMetrics {
+    /// Column names, in the order of [`EpisodeMetrics::values`]
+    pub const COLUMNS: [&'static str; 11] = [
+        "episode",
+        "steps",
+        "return",
+        "distance",
+        "epsilon",
+        "alpha",
+        "td_error",
+        "states_visited",
+        "eval_mean",
+        "eval_median",
+        "eval_crash_rate",
+    ];
+
+    /// Every metric as a number; evaluation columns are `None` between
+    /// evaluations
+    pub fn values(&self) -> [Option<f64>; 11] {
+        let eval = self.evaluation;
+        [
+            Some(self.episode as f64),
+            Some(self.steps as f64),
+            Some(self.total_reward),
+            Some(f64::from(self.distance)),
+            Some(self.epsilon),
+            Some(self.alpha),
+            Some(self.mean_abs_td_error),
+            Some(self.states_visited as f64),
+            eval.map(|e| e.mean),
+            eval.map(|e| f64::from(e.median)),
+            eval.map(|e| e.crash_rate),
+        ]
+    }
+}
+
+/// File format of a metrics log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricsFormat {
+    /// Comma-separated with a header row
+    Csv,
+    /// One JSON object per line
+    Jsonl,
+}
+
+impl MetricsFormat {
+    /// Short name, also the file extension
+    pub fn name(self) -> &'static str {
+        match self {
+            MetricsFormat::Csv => "csv",
+            MetricsFormat::Jsonl => "jsonl",
+        }
+    }
+
+    /// Picks the format from a file's extension
+    pub fn from_path(path: &str) -> Result<Self> {
+        let extension = Path::new(path).extension().and_then(|ext| ext.to_str());
+        [MetricsFormat::Csv, MetricsFormat::Jsonl]
+            .into_iter()
+            .find(|format| Some(format.name()) == extension)
+            .ok_or_else(|| {
+                GameError::InvalidConfig(format!(
+                    "metrics file must end in .csv or .jsonl: {}",
+                    path
+                ))
+            })
+    }
+}
+
+impl fmt::Display for MetricsFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Writes one row of metrics per episode
+pub struct MetricsLog<W: Write> {
+    writer: W,
+    format: MetricsFormat,
+    header_written: bool,
+}
+
+impl MetricsLog<BufWriter<File>> {
+    /// Creates a log file, in the format its extension names
+    pub fn create(path: &str) -> Result<Self> {
+        let format = MetricsFormat::from_path(path)?;
+        Ok(Self::new(BufWriter::new(File::create(path)?), format))
+    }
+
+    /// Opens a log file to continue it, creating it if needed
+    pub fn append(path: &str) -> Result<Self> {
+        let format = MetricsFormat::from_path(path)?;
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let mut log = Self::new(BufWriter::new(file), format);
+        log.header_written = fs::metadata(path)?.len() > 0;
+        Ok(log)
+    }
+}
+
+impl<W: Write> MetricsLog<W> {
+    /// Creates a log writing to any writer
+    pub fn new(writer: W, format: MetricsFormat) -> Self {
+        Self {
+            writer,
+            format,
+            header_written: false,
+        }
+    }
+
+    /// Appends the metrics of an episode
+    pub fn record(&mut self, metrics: &EpisodeMetrics) -> Result<()> {
+        let values = metrics.values();
+        match self.format {
+            MetricsFormat::Csv => {
+                if !self.header_written {
+                    writeln!(self.writer, "{}", EpisodeMetrics::COLUMNS.join(","))?;
+                    self.header_written = true;
+                }
+                let cells: Vec<String> = values
+                    .iter()
+                    .map(|value| match value {
+                        Some(v) if v.is_finite() => v.to_string(),
+                        _ => String::new(),
+                    })
+                    .collect();
+                writeln!(self.writer, "{}", cells.join(","))?;
+            }
+            MetricsFormat::Jsonl => {
+                let fields: Vec<String> = EpisodeMetrics::COLUMNS
+                    .iter()
+                    .zip(values)
+                    .filter_map(|(name, value)| {
+                        value.map(|v| format!("\"{}\":{}", name, format_number(v)))
+                    })
+                    .collect();
+                writeln!(self.writer, "{{{}}}", fields.join(","))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes buffered rows through
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Returns the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Formats a number for JSON, which has no NaN or infinity
+fn format_number(value: f64) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        "null".to_string()
+    }
+}
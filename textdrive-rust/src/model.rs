@@ -0,0 +1,799 @@
+use crate::challenge::Date;
+use crate::config::ResumeSettings;
+use crate::encoder::{Encoder, StateEncoder};
+use crate::error::{GameError, Result};
+use crate::hyperparams::Hyperparams;
+use crate::qlearning::{Agent, NUM_ACTIONS};
+use crate::rng::SimRng;
+use crate::stopping::StopReason;
+use crate::storage::{QRow, QTable, StorageKind};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::ops::Range;
+
+/// Marks a chunked model file
+pub const MODEL_MAGIC: &[u8; 4] = b"TDQT";
+/// Version written by [`write_model`]
+pub const MODEL_VERSION: u16 = 2;
+
+const META_CHUNK: &[u8; 4] = b"META";
+const QTAB_CHUNK: &[u8; 4] = b"QTAB";
+const VISITS_CHUNK: &[u8; 4] = b"VSTS";
+/// Second Double Q table of a checkpoint
+const SECOND_TABLE_CHUNK: &[u8; 4] = b"QTB2";
+/// Generator states of a checkpoint
+const RNG_CHUNK: &[u8; 4] = b"RNGS";
+const END_CHUNK: &[u8; 4] = b"END ";
+
+pub(crate) const ROW_BYTES: usize = NUM_ACTIONS * 8;
+pub(crate) const SPARSE_ROW_BYTES: usize = 8 + ROW_BYTES;
+/// State and visit count
+const VISIT_BYTES: usize = 16;
+/// Exploration and course generator states
+const RNG_BYTES: usize = 2 * 4 * 8;
+/// epsilon, best score and episode count after the table of old formats
+const LEGACY_TRAILER_BYTES: usize = 8 + 4 + 8;
+
+/// What a model file says about the agent it holds
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelInfo {
+    /// Format version: 0 for headerless files, 2 for the chunked format
+    pub version: u16,
+    pub encoder: Encoder,
+    pub storage: StorageKind,
+    /// Number of states the Q-table covers
+    pub states: usize,
+    /// Number of states stored in the file
+    pub stored: usize,
+    /// Number of states with a visit count, if visits were tracked
+    pub visited: Option<usize>,
+    pub episodes: u64,
+    pub steps: u64,
+    pub best_score: i32,
+    pub epsilon: f64,
+    /// Day the model was saved, unknown before version 2
+    pub trained: Option<Date>,
+    /// Why training ended, if it has
+    pub stop_reason: Option<StopReason>,
+    /// Hyperparameters, the defaults before version 2
+    pub params: Hyperparams,
+    /// Settings the run that saved a checkpoint used
+    pub settings: Option<ResumeSettings>,
+}
+
+impl ModelInfo {
+    /// Reads the description of a model file
+    pub fn load(filename: &str) -> Result<Self> {
+        read_model(&fs::read(filename)?).map(|(_, info)| info)
+    }
+}
+
+/// Writes an agent in the current format
+///
+/// The file is the magic bytes and version, then `META` (key=value lines),
+/// `QTAB` (the table), `VSTS` (visit counts, if tracked) and `END ` (a CRC-32
+/// of everything before it) chunks.
+/// Each chunk is a 4-byte tag and a little-endian u64 length followed by its
+/// payload; readers skip chunks they don't know.
+pub fn write_model(agent: &Agent, trained: Option<Date>, writer: impl Write) -> Result<()> {
+    write_file(agent, trained, None, writer)
+}
+
+/// Writes everything training needs to continue where it stopped
+///
+/// On top of a regular model, a checkpoint keeps both Double Q tables as
+/// they are (`QTAB` and `QTB2`) and the states of the agent's and the
+/// trainer's course generators (`RNGS`), and its metadata records the
+/// run's [`ResumeSettings`]. It still loads as a model.
+pub fn write_checkpoint(
+    agent: &Agent,
+    courses: &SimRng,
+    settings: &ResumeSettings,
+    writer: impl Write,
+) -> Result<()> {
+    write_file(agent, None, Some((courses, settings)), writer)
+}
+
+/// Reads a checkpoint back into the agent and the course generator
+pub fn read_checkpoint(bytes: &[u8]) -> Result<(Agent, SimRng)> {
+    let (info, sections) = parse_chunked(bytes)?;
+    let Some(range) = sections.rngs.clone() else {
+        return Err(GameError::InvalidAgentData(
+            "not a checkpoint: missing RNGS chunk".to_string(),
+        ));
+    };
+    let agent = read_agent(bytes, info, sections)?;
+    let (_, courses) = read_rngs(&bytes[range])?;
+    Ok((agent, courses))
+}
+
+fn write_file(
+    agent: &Agent,
+    trained: Option<Date>,
+    checkpoint: Option<(&SimRng, &ResumeSettings)>,
+    writer: impl Write,
+) -> Result<()> {
+    let mut out = ChecksumWriter::new(writer);
+    out.write_all(MODEL_MAGIC)?;
+    out.write_all(&MODEL_VERSION.to_le_bytes())?;
+    out.write_all(&0u16.to_le_bytes())?;
+
+    let meta = metadata(agent, trained, checkpoint.map(|(_, settings)| settings));
+    write_chunk_header(&mut out, META_CHUNK, meta.len())?;
+    out.write_all(meta.as_bytes())?;
+
+    match (checkpoint, &agent.q2) {
+        // Playing only needs the averaged Double Q values
+        (None, _) => write_table(
+            &mut out,
+            QTAB_CHUNK,
+            &agent.q,
+            || agent.stored_states(),
+            |s| agent.q_values(s),
+        )?,
+        (Some(_), q2) => {
+            write_table(
+                &mut out,
+                QTAB_CHUNK,
+                &agent.q,
+                || agent.q.stored_states(),
+                |s| agent.q[s],
+            )?;
+            if let Some(q2) = q2 {
+                write_table(
+                    &mut out,
+                    SECOND_TABLE_CHUNK,
+                    q2,
+                    || q2.stored_states(),
+                    |s| q2[s],
+                )?;
+            }
+        }
+    }
+
+    if let Some(ref visits) = agent.visits {
+        let mut visits: Vec<(usize, u64)> = visits.iter().map(|(&s, &n)| (s, n)).collect();
+        visits.sort_unstable();
+        write_chunk_header(&mut out, VISITS_CHUNK, 8 + visits.len() * VISIT_BYTES)?;
+        out.write_all(&(visits.len() as u64).to_le_bytes())?;
+        for (state, count) in visits {
+            out.write_all(&(state as u64).to_le_bytes())?;
+            out.write_all(&count.to_le_bytes())?;
+        }
+    }
+
+    if let Some((courses, _)) = checkpoint {
+        write_chunk_header(&mut out, RNG_CHUNK, RNG_BYTES)?;
+        for word in agent.rng.state().iter().chain(&courses.state()) {
+            out.write_all(&word.to_le_bytes())?;
+        }
+    }
+
+    let checksum = out.hasher.clone().finalize();
+    let mut writer = out.inner;
+    writer.write_all(END_CHUNK)?;
+    writer.write_all(&4u64.to_le_bytes())?;
+    writer.write_all(&checksum.to_le_bytes())?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads an agent from the bytes of a chunked or headerless model file
+pub fn read_model(bytes: &[u8]) -> Result<(Agent, ModelInfo)> {
+    if bytes.starts_with(MODEL_MAGIC) {
+        read_chunked(bytes)
+    } else {
+        read_v0(bytes)
+    }
+}
+
+/// Format version of model data from its first bytes
+///
+/// Returns `None` if the data is too short to tell.
+pub fn format_version(bytes: &[u8]) -> Option<u16> {
+    if bytes.starts_with(MODEL_MAGIC) {
+        let version = bytes.get(MODEL_MAGIC.len()..MODEL_MAGIC.len() + 2)?;
+        Some(u16::from_le_bytes([version[0], version[1]]))
+    } else if bytes.len() < MODEL_MAGIC.len() {
+        None
+    } else {
+        Some(0)
+    }
+}
+
+/// Writes a file as `<filename>.tmp`, then renames it over `filename`
+///
+/// Readers, memory-mapped policies included, keep the old file until the new
+/// one is complete.
+pub(crate) fn replace_file(
+    filename: &str,
+    write: impl FnOnce(BufWriter<fs::File>) -> Result<()>,
+) -> Result<()> {
+    let partial = format!("{}.tmp", filename);
+    let result = fs::File::create(&partial)
+        .map_err(GameError::from)
+        .and_then(|file| write(BufWriter::new(file)));
+    if let Err(err) = result {
+        let _ = fs::remove_file(&partial);
+        return Err(err);
+    }
+    fs::rename(&partial, filename)?;
+    Ok(())
+}
+
+/// Rewrites a model file in the current format
+///
+/// Returns the version the file had; files already current are left alone.
+pub fn migrate(filename: &str) -> Result<u16> {
+    let (agent, info) = read_model(&fs::read(filename)?)?;
+    if info.version < MODEL_VERSION {
+        replace_file(filename, |writer| write_model(&agent, info.trained, writer))?;
+    }
+    Ok(info.version)
+}
+
+fn metadata(agent: &Agent, trained: Option<Date>, settings: Option<&ResumeSettings>) -> String {
+    let params = &agent.params;
+    let mut fields = vec![
+        ("encoder", agent.encoder.id()),
+        ("storage", agent.q.kind().to_string()),
+        ("states", agent.q.len().to_string()),
+        ("episodes", agent.episodes.to_string()),
+        ("steps", agent.clock.steps.to_string()),
+        ("best_score", agent.best_score.to_string()),
+        ("epsilon", agent.epsilon.to_string()),
+        ("alpha_schedule", params.alpha.to_string()),
+        ("epsilon_schedule", params.epsilon.to_string()),
+        ("gamma", params.gamma.to_string()),
+        ("collision_reward", params.collision_reward.to_string()),
+        ("survival_reward", params.survival_reward.to_string()),
+    ];
+    if let Some(date) = trained {
+        fields.push(("trained", date.to_string()));
+    }
+    if let Some(reason) = agent.stop_reason {
+        fields.push(("stop_reason", reason.to_string()));
+    }
+    if let Some(settings) = settings {
+        fields.push(("learner", settings.learner.to_string()));
+        if let Some(n) = settings.n_step {
+            fields.push(("n_step", n.to_string()));
+        }
+        if let Some(lambda) = settings.lambda {
+            fields.push(("lambda", lambda.to_string()));
+        }
+        if let Some(trace) = settings.trace {
+            fields.push(("trace", trace.to_string()));
+        }
+        fields.push(("max_steps", settings.max_steps.to_string()));
+    }
+
+    fields
+        .into_iter()
+        .map(|(key, value)| format!("{}={}\n", key, value))
+        .collect()
+}
+
+/// Byte ranges of the data chunks of a chunked file
+pub(crate) struct Sections {
+    /// Q-table, whose size has been checked against the metadata
+    pub table: Range<usize>,
+    /// Visit counts, whose size has been checked
+    pub visits: Option<Range<usize>>,
+    /// Second Double Q table of a checkpoint, whose size has been checked
+    pub second_table: Option<Range<usize>>,
+    /// Generator states of a checkpoint, whose size has been checked
+    pub rngs: Option<Range<usize>>,
+}
+
+/// Checks a chunked file and reads its metadata
+pub(crate) fn parse_chunked(bytes: &[u8]) -> Result<(ModelInfo, Sections)> {
+    if !bytes.starts_with(MODEL_MAGIC) {
+        return Err(match format_version(bytes) {
+            Some(version) => GameError::UnsupportedVersion(version),
+            None => truncated(),
+        });
+    }
+    let mut reader = SliceReader::new(bytes);
+    reader.take(MODEL_MAGIC.len())?;
+    let version = reader.u16()?;
+    if version != MODEL_VERSION {
+        return Err(GameError::UnsupportedVersion(version));
+    }
+    reader.u16()?;
+
+    // Collect the chunks up to the checksum before trusting any of them
+    let mut chunks = Vec::new();
+    loop {
+        let start = reader.pos;
+        let tag = reader.take(4)?;
+        let len = reader.u64()?;
+        let len = usize::try_from(len).map_err(|_| truncated())?;
+        let payload_start = reader.pos;
+        reader.take(len)?;
+        let payload = payload_start..reader.pos;
+        if tag == END_CHUNK {
+            let expected = SliceReader::new(&bytes[payload]).u32()?;
+            let found = crc32fast::hash(&bytes[..start]);
+            if expected != found {
+                return Err(GameError::ChecksumMismatch { expected, found });
+            }
+            break;
+        }
+        chunks.push((tag, payload));
+    }
+
+    let chunk = |name: &[u8; 4]| {
+        chunks
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, payload)| payload.clone())
+            .ok_or_else(|| {
+                GameError::InvalidAgentData(format!(
+                    "missing {} chunk",
+                    String::from_utf8_lossy(name)
+                ))
+            })
+    };
+
+    let meta = parse_metadata(&bytes[chunk(META_CHUNK)?])?;
+    let encoder: Encoder = meta_value(&meta, "encoder")?;
+    let storage: StorageKind = meta_value(&meta, "storage")?;
+    let states: usize = meta_value(&meta, "states")?;
+    check_shape(&encoder, states)?;
+
+    let table = chunk(QTAB_CHUNK)?;
+    let stored = table_size(&bytes[table.clone()], storage, states)?;
+    let second_table = chunk(SECOND_TABLE_CHUNK).ok();
+    if let Some(ref range) = second_table {
+        table_size(&bytes[range.clone()], storage, states)?;
+    }
+    let rngs = chunk(RNG_CHUNK).ok();
+    if rngs.as_ref().is_some_and(|range| range.len() != RNG_BYTES) {
+        return Err(truncated());
+    }
+
+    let visits = chunk(VISITS_CHUNK).ok();
+    let visited = match visits {
+        Some(ref range) => {
+            let mut reader = SliceReader::new(&bytes[range.clone()]);
+            let count = reader.u64()? as usize;
+            if reader.remaining() != count.saturating_mul(VISIT_BYTES) {
+                return Err(truncated());
+            }
+            Some(count)
+        }
+        None => None,
+    };
+
+    let trained = match meta.get("trained") {
+        Some(date) => Some(parse_value("trained", date)?),
+        None => None,
+    };
+    let stop_reason = match meta.get("stop_reason") {
+        Some(reason) => Some(reason.parse()?),
+        None => None,
+    };
+    let settings = match meta.get("learner") {
+        Some(learner) => Some(ResumeSettings {
+            learner: parse_value("learner", learner)?,
+            n_step: optional_meta_value(&meta, "n_step")?,
+            lambda: optional_meta_value(&meta, "lambda")?,
+            trace: optional_meta_value(&meta, "trace")?,
+            max_steps: meta_value(&meta, "max_steps")?,
+        }),
+        None => None,
+    };
+    let info = ModelInfo {
+        version,
+        encoder,
+        storage,
+        states,
+        stored,
+        visited,
+        episodes: meta_value(&meta, "episodes")?,
+        steps: meta_value(&meta, "steps")?,
+        best_score: meta_value(&meta, "best_score")?,
+        epsilon: finite("epsilon", meta_value(&meta, "epsilon")?)?,
+        trained,
+        stop_reason,
+        settings,
+        params: Hyperparams {
+            alpha: meta_value(&meta, "alpha_schedule")?,
+            epsilon: meta_value(&meta, "epsilon_schedule")?,
+            gamma: meta_value(&meta, "gamma")?,
+            collision_reward: meta_value(&meta, "collision_reward")?,
+            survival_reward: meta_value(&meta, "survival_reward")?,
+        },
+    };
+    let sections = Sections {
+        table,
+        visits,
+        second_table,
+        rngs,
+    };
+    Ok((info, sections))
+}
+
+/// Number of states stored in a table chunk, checked against its size
+fn table_size(table: &[u8], storage: StorageKind, states: usize) -> Result<usize> {
+    match storage {
+        StorageKind::Dense => {
+            if table.len() != states * ROW_BYTES {
+                return Err(GameError::ShapeMismatch {
+                    expected: states,
+                    found: table.len() / ROW_BYTES,
+                });
+            }
+            Ok(states)
+        }
+        StorageKind::Sparse => {
+            let mut reader = SliceReader::new(table);
+            let count = reader.u64()? as usize;
+            if reader.remaining() != count.saturating_mul(SPARSE_ROW_BYTES) {
+                return Err(truncated());
+            }
+            Ok(count)
+        }
+    }
+}
+
+/// Checks the rows of a table chunk without reading them into a table
+///
+/// Values must be finite, and sparse entries in range and sorted by state,
+/// so the table can be searched in place.
+pub(crate) fn check_table(table: &[u8], storage: StorageKind, states: usize) -> Result<()> {
+    let mut reader = SliceReader::new(table);
+    match storage {
+        StorageKind::Dense => {
+            for state in 0..states {
+                read_row(&mut reader, state)?;
+            }
+        }
+        StorageKind::Sparse => {
+            let count = reader.u64()?;
+            let mut previous = None;
+            for _ in 0..count {
+                let state = reader.u64()? as usize;
+                if state >= states || previous.is_some_and(|previous| state <= previous) {
+                    return Err(GameError::InvalidAgentData(format!(
+                        "state {} out of range or out of order",
+                        state
+                    )));
+                }
+                read_row(&mut reader, state)?;
+                previous = Some(state);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_chunked(bytes: &[u8]) -> Result<(Agent, ModelInfo)> {
+    let (info, sections) = parse_chunked(bytes)?;
+    let agent = read_agent(bytes, info.clone(), sections)?;
+    Ok((agent, info))
+}
+
+fn read_agent(bytes: &[u8], info: ModelInfo, sections: Sections) -> Result<Agent> {
+    let mut agent = Agent::with_storage(info.encoder, info.params, info.storage);
+    read_table(&mut agent.q, &bytes[sections.table])?;
+    if let Some(range) = sections.second_table {
+        let mut q2 = QTable::new(info.storage, info.states);
+        read_table(&mut q2, &bytes[range])?;
+        agent.q2 = Some(q2);
+    }
+    if let Some(range) = sections.rngs {
+        agent.rng = read_rngs(&bytes[range])?.0;
+    }
+
+    if let Some(range) = sections.visits {
+        let mut reader = SliceReader::new(&bytes[range]);
+        let count = reader.u64()?;
+        let mut visits = HashMap::new();
+        for _ in 0..count {
+            let state = reader.u64()? as usize;
+            if state >= agent.q.len() {
+                return Err(GameError::InvalidAgentData(format!(
+                    "visited state {} out of range",
+                    state
+                )));
+            }
+            visits.insert(state, reader.u64()?);
+        }
+        agent.visits = Some(visits);
+    }
+
+    agent.epsilon = info.epsilon;
+    agent.best_score = info.best_score;
+    agent.episodes = info.episodes;
+    agent.stop_reason = info.stop_reason;
+    agent.clock.episodes = info.episodes;
+    agent.clock.steps = info.steps;
+    agent.alpha = agent.params.alpha.value_at(agent.clock);
+
+    Ok(agent)
+}
+
+/// Exploration and course generators of a checkpoint
+fn read_rngs(payload: &[u8]) -> Result<(SimRng, SimRng)> {
+    let mut reader = SliceReader::new(payload);
+    let mut next = || -> Result<SimRng> {
+        let state = [reader.u64()?, reader.u64()?, reader.u64()?, reader.u64()?];
+        SimRng::from_state(state)
+            .ok_or_else(|| GameError::InvalidAgentData("invalid generator state".to_string()))
+    };
+    Ok((next()?, next()?))
+}
+
+/// Headerless files hold the default window encoder's table
+fn read_v0(bytes: &[u8]) -> Result<(Agent, ModelInfo)> {
+    let mut reader = SliceReader::new(bytes);
+    let mut agent = Agent::new();
+    let table = reader.take(bytes.len().saturating_sub(LEGACY_TRAILER_BYTES))?;
+    let stored = read_dense(&mut agent.q, table)?;
+    read_legacy_trailer(&mut agent, &mut reader)?;
+
+    let info = info(&agent, 0, stored, None);
+    Ok((agent, info))
+}
+
+fn read_legacy_trailer(agent: &mut Agent, reader: &mut SliceReader) -> Result<()> {
+    agent.epsilon = finite("epsilon", reader.f64()?)?;
+    agent.best_score = reader.i32()?;
+    agent.episodes = reader.u64()?;
+    // Per-step progress isn't stored, so schedules resume from the episode count
+    agent.clock.episodes = agent.episodes;
+    Ok(())
+}
+
+fn read_table(q: &mut QTable, table: &[u8]) -> Result<usize> {
+    match q.kind() {
+        StorageKind::Dense => read_dense(q, table),
+        StorageKind::Sparse => read_sparse(q, table),
+    }
+}
+
+fn read_dense(q: &mut QTable, table: &[u8]) -> Result<usize> {
+    let states = q.len();
+    if table.len() != states * ROW_BYTES {
+        return Err(GameError::ShapeMismatch {
+            expected: states,
+            found: table.len() / ROW_BYTES,
+        });
+    }
+
+    let mut reader = SliceReader::new(table);
+    for state in 0..states {
+        q[state] = read_row(&mut reader, state)?;
+    }
+    Ok(states)
+}
+
+fn read_sparse(q: &mut QTable, table: &[u8]) -> Result<usize> {
+    let mut reader = SliceReader::new(table);
+    let count = reader.u64()? as usize;
+    if reader.remaining() != count.saturating_mul(SPARSE_ROW_BYTES) {
+        return Err(truncated());
+    }
+
+    for _ in 0..count {
+        let state = reader.u64()? as usize;
+        if state >= q.len() {
+            return Err(GameError::InvalidAgentData(format!(
+                "state {} out of range",
+                state
+            )));
+        }
+        q[state] = read_row(&mut reader, state)?;
+    }
+    Ok(count)
+}
+
+fn read_row(reader: &mut SliceReader, state: usize) -> Result<[f64; NUM_ACTIONS]> {
+    let mut row = [0.0; NUM_ACTIONS];
+    for value in row.iter_mut() {
+        *value = reader.f64()?;
+        if !value.is_finite() {
+            return Err(GameError::NonFiniteValue(format!(
+                "Q-value in state {}",
+                state
+            )));
+        }
+    }
+    Ok(row)
+}
+
+fn write_row(writer: &mut impl Write, row: &[f64; NUM_ACTIONS]) -> Result<()> {
+    for value in row {
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// Writes a table chunk: every row of a dense table, or the given states of
+/// a sparse one prefixed by their count
+fn write_table(
+    writer: &mut impl Write,
+    tag: &[u8; 4],
+    q: &QTable,
+    stored_states: impl FnOnce() -> Vec<usize>,
+    row: impl Fn(usize) -> QRow,
+) -> Result<()> {
+    match q.kind() {
+        StorageKind::Dense => {
+            write_chunk_header(writer, tag, q.len() * ROW_BYTES)?;
+            for state in 0..q.len() {
+                write_row(writer, &row(state))?;
+            }
+        }
+        StorageKind::Sparse => {
+            let states = stored_states();
+            write_chunk_header(writer, tag, 8 + states.len() * SPARSE_ROW_BYTES)?;
+            writer.write_all(&(states.len() as u64).to_le_bytes())?;
+            for state in states {
+                writer.write_all(&(state as u64).to_le_bytes())?;
+                write_row(writer, &row(state))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_chunk_header(writer: &mut impl Write, tag: &[u8; 4], len: usize) -> Result<()> {
+    writer.write_all(tag)?;
+    writer.write_all(&(len as u64).to_le_bytes())?;
+    Ok(())
+}
+
+fn parse_metadata(payload: &[u8]) -> Result<BTreeMap<String, String>> {
+    let text = std::str::from_utf8(payload)
+        .map_err(|_| GameError::InvalidAgentData("metadata is not UTF-8".to_string()))?;
+    Ok(text
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect())
+}
+
+fn meta_value<T: std::str::FromStr>(meta: &BTreeMap<String, String>, key: &str) -> Result<T> {
+    let value = meta
+        .get(key)
+        .ok_or_else(|| GameError::InvalidAgentData(format!("missing {}", key)))?;
+    parse_value(key, value)
+}
+
+fn optional_meta_value<T: std::str::FromStr>(
+    meta: &BTreeMap<String, String>,
+    key: &str,
+) -> Result<Option<T>> {
+    meta.get(key)
+        .map(|value| parse_value(key, value))
+        .transpose()
+}
+
+fn parse_value<T: std::str::FromStr>(key: &str, value: &str) -> Result<T> {
+    value
+        .parse()
+        .map_err(|_| GameError::InvalidAgentData(format!("invalid {}: {}", key, value)))
+}
+
+fn check_shape(encoder: &Encoder, states: usize) -> Result<()> {
+    if states != encoder.state_count() {
+        return Err(GameError::ShapeMismatch {
+            expected: encoder.state_count(),
+            found: states,
+        });
+    }
+    Ok(())
+}
+
+fn finite(key: &str, value: f64) -> Result<f64> {
+    if value.is_finite() {
+        Ok(value)
+    } else {
+        Err(GameError::NonFiniteValue(key.to_string()))
+    }
+}
+
+fn info(agent: &Agent, version: u16, stored: usize, trained: Option<Date>) -> ModelInfo {
+    ModelInfo {
+        version,
+        encoder: agent.encoder.clone(),
+        storage: agent.q.kind(),
+        states: agent.q.len(),
+        stored,
+        visited: None,
+        episodes: agent.episodes,
+        steps: agent.clock.steps,
+        best_score: agent.best_score,
+        epsilon: agent.epsilon,
+        trained,
+        stop_reason: None,
+        params: agent.params,
+        settings: None,
+    }
+}
+
+fn truncated() -> GameError {
+    GameError::InvalidAgentData("file is truncated".to_string())
+}
+
+/// Reads little-endian values, reporting truncation instead of an I/O error
+struct SliceReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> SliceReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.pos
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if len > self.remaining() {
+            return Err(truncated());
+        }
+        let slice = &self.bytes[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(slice)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut buf = [0u8; N];
+        buf.copy_from_slice(self.take(N)?);
+        Ok(buf)
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn i32(&mut self) -> Result<i32> {
+        Ok(i32::from_le_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    fn f64(&mut self) -> Result<f64> {
+        Ok(f64::from_le_bytes(self.array()?))
+    }
+}
+
+/// Hashes everything written through it
+struct ChecksumWriter<W> {
+    inner: W,
+    hasher: crc32fast::Hasher,
+}
+
+impl<W: Write> ChecksumWriter<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            hasher: crc32fast::Hasher::new(),
+        }
+    }
+}
+
+impl<W: Write> Write for ChecksumWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
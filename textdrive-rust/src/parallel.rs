@@ -0,0 +1,107 @@
+use crate::error::Result;
+use crate::merge::{merge, MergeMode};
+use crate::qlearning::Agent;
+use crate::rng::SimRng;
+use rand::RngCore;
+use std::collections::HashMap;
+use std::thread;
+
+/// Default episodes each worker runs between merges
+pub const DEFAULT_SYNC_EVERY: usize = 100;
+
+/// Trains copies of `agent` on `workers` threads for one round, then merges
+/// them back into it
+///
+/// Each worker starts from a clone of the agent with its own exploration
+/// seed and course stream, both drawn from `courses`, and runs its share of
+/// `episodes` through `train`, which gets the worker's agent, its courses
+/// and how many episodes to run. The workers' tables are then averaged,
+/// weighted by how often each visited a state during the round. Because
+/// seeds come from `courses` and workers are merged in a fixed order, the
+/// same seed and worker count always give the same tables.
+///
+/// Returns what `train` returned for every episode, interleaved across
+/// workers.
+pub fn run_round<T, F>(
+    agent: &mut Agent,
+    courses: &mut SimRng,
+    workers: usize,
+    episodes: usize,
+    train: F,
+) -> Result<Vec<T>>
+where
+    T: Send,
+    F: Fn(&mut Agent, &mut SimRng, usize) -> Vec<T> + Sync,
+{
+    let workers = workers.clamp(1, episodes.max(1));
+    let starts: Vec<(Agent, SimRng)> = (0..workers)
+        .map(|_| {
+            let mut worker = agent.clone();
+            worker.seed(courses.next_u64());
+            if worker.visits.is_some() {
+                worker.visits = Some(HashMap::new());
+            }
+            (worker, SimRng::new(courses.next_u64()))
+        })
+        .collect();
+
+    let train = &train;
+    let results: Vec<(Agent, Vec<T>)> = thread::scope(|scope| {
+        let handles: Vec<_> = starts
+            .into_iter()
+            .enumerate()
+            .map(|(i, (mut worker, mut worker_courses))| {
+                let count = episodes / workers + usize::from(i < episodes % workers);
+                scope.spawn(move || {
+                    let results = train(&mut worker, &mut worker_courses, count);
+                    (worker, results)
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("training worker panicked"))
+            .collect()
+    });
+
+    let (trained, outputs): (Vec<Agent>, Vec<Vec<T>>) = results.into_iter().unzip();
+    absorb(agent, &trained)?;
+
+    let longest = outputs.iter().map(Vec::len).max().unwrap_or(0);
+    let mut outputs: Vec<_> = outputs.into_iter().map(Vec::into_iter).collect();
+    let mut interleaved = Vec::with_capacity(episodes);
+    for _ in 0..longest {
+        interleaved.extend(outputs.iter_mut().filter_map(Iterator::next));
+    }
+    Ok(interleaved)
+}
+
+/// Folds workers trained from clones of `agent` back into it
+///
+/// Episode and step counts add up, so schedules advance as if one agent had
+/// run every worker's episodes. Double Q-learning's two tables both take the
+/// merged values.
+pub fn absorb(agent: &mut Agent, workers: &[Agent]) -> Result<()> {
+    let models: Vec<(&Agent, f64)> = workers.iter().map(|worker| (worker, 1.0)).collect();
+    let merged = merge(&models, MergeMode::Average)?;
+
+    let (start, start_episodes) = (agent.clock, agent.episodes);
+    for worker in workers {
+        agent.episodes += worker.episodes - start_episodes;
+        agent.clock.episodes += worker.clock.episodes - start.episodes;
+        agent.clock.steps += worker.clock.steps - start.steps;
+        agent.best_score = agent.best_score.max(worker.best_score);
+    }
+    agent.refresh_schedules();
+
+    agent.q = merged.q;
+    if let Some(ref mut q2) = agent.q2 {
+        *q2 = agent.q.clone();
+    }
+    if let (Some(total), Some(round)) = (agent.visits.as_mut(), merged.visits) {
+        for (state, count) in round {
+            *total.entry(state).or_insert(0) += count;
+        }
+    }
+    Ok(())
+}
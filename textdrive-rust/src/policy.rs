@@ -0,0 +1,148 @@
+use crate::encoder::{Encoder, StateEncoder};
+use crate::error::{GameError, Result};
+use crate::export::{QuantizedPolicy, POLICY_MAGIC};
+use crate::game::Game;
+use crate::model::{self, ModelInfo, MODEL_VERSION, ROW_BYTES, SPARSE_ROW_BYTES};
+use crate::qlearning::{best_action, Action, Agent, NUM_ACTIONS};
+use crate::storage::StorageKind;
+use memmap2::Mmap;
+use std::fs::File;
+use std::io::Read;
+use std::ops::Range;
+
+/// Read-only view of a trained model for playing
+pub trait Policy {
+    /// Encoder that maps games to this policy's states
+    fn encoder(&self) -> &Encoder;
+
+    /// Q-values of a state
+    fn q_values(&self, state: usize) -> [f64; NUM_ACTIONS];
+
+    /// Encodes the game into a state
+    fn state(&self, game: &Game) -> usize {
+        self.encoder().encode(game)
+    }
+
+    /// Greedy action in a state
+    fn best_action(&self, state: usize) -> Action {
+        best_action(&self.q_values(state))
+    }
+}
+
+impl Policy for Agent {
+    fn encoder(&self) -> &Encoder {
+        &self.encoder
+    }
+
+    fn q_values(&self, state: usize) -> [f64; NUM_ACTIONS] {
+        Agent::q_values(self, state)
+    }
+}
+
+/// Policy reading Q-values straight from a memory-mapped model file
+///
+/// Opening checks the header, the checksum of the whole file and that every
+/// value is finite and sparse entries are sorted. Rows are then decoded on
+/// lookup instead of being copied into an [`Agent`], and the
+/// pages are shared with other processes. Only the current format can be
+/// mapped.
+pub struct MappedPolicy {
+    map: Mmap,
+    table: Range<usize>,
+    info: ModelInfo,
+}
+
+impl MappedPolicy {
+    /// Maps a model file
+    pub fn open(filename: &str) -> Result<Self> {
+        let file = File::open(filename)?;
+        // SAFETY: the map is read-only. Models are saved to a temporary file
+        // that is renamed over the old one (`model::replace_file`), so the
+        // mapped bytes don't change while in use.
+        let map = unsafe { Mmap::map(&file)? };
+        let (info, sections) = model::parse_chunked(&map)?;
+        if sections.second_table.is_some() {
+            return Err(GameError::InvalidAgentData(
+                "checkpoints with two Double Q tables can't be mapped".to_string(),
+            ));
+        }
+        model::check_table(&map[sections.table.clone()], info.storage, info.states)?;
+        Ok(Self {
+            map,
+            table: sections.table,
+            info,
+        })
+    }
+
+    /// Description of the mapped model
+    pub fn info(&self) -> &ModelInfo {
+        &self.info
+    }
+
+    fn dense_row(&self, state: usize) -> [f64; NUM_ACTIONS] {
+        let start = self.table.start + state * ROW_BYTES;
+        decode_row(&self.map[start..start + ROW_BYTES])
+    }
+
+    /// Binary search over the state-sorted sparse entries
+    fn sparse_row(&self, state: usize) -> [f64; NUM_ACTIONS] {
+        let entries = &self.map[self.table.start + 8..self.table.end];
+        let key = |i: usize| u64::from_le_bytes(word(entries, i * SPARSE_ROW_BYTES)) as usize;
+
+        let (mut low, mut high) = (0, self.info.stored);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match key(mid).cmp(&state) {
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Greater => high = mid,
+                std::cmp::Ordering::Equal => {
+                    let start = mid * SPARSE_ROW_BYTES + 8;
+                    return decode_row(&entries[start..start + ROW_BYTES]);
+                }
+            }
+        }
+        [0.0; NUM_ACTIONS]
+    }
+}
+
+impl Policy for MappedPolicy {
+    fn encoder(&self) -> &Encoder {
+        &self.info.encoder
+    }
+
+    fn q_values(&self, state: usize) -> [f64; NUM_ACTIONS] {
+        assert!(state < self.info.states, "state {} out of range", state);
+        match self.info.storage {
+            StorageKind::Dense => self.dense_row(state),
+            StorageKind::Sparse => self.sparse_row(state),
+        }
+    }
+}
+
+/// Loads a model for playing
+///
+/// Exported policies are read as they are, models in the current format are
+/// memory-mapped and older ones are read into an [`Agent`].
+pub fn load_policy(filename: &str) -> Result<Box<dyn Policy>> {
+    let mut header = Vec::with_capacity(6);
+    File::open(filename)?.take(6).read_to_end(&mut header)?;
+
+    if header.starts_with(POLICY_MAGIC) {
+        Ok(Box::new(QuantizedPolicy::load(filename)?))
+    } else if model::format_version(&header) == Some(MODEL_VERSION) {
+        Ok(Box::new(MappedPolicy::open(filename)?))
+    } else {
+        Ok(Box::new(Agent::load(filename)?))
+    }
+}
+
+fn decode_row(bytes: &[u8]) -> [f64; NUM_ACTIONS] {
+    std::array::from_fn(|action| f64::from_le_bytes(word(bytes, action * 8)))
+}
+
+/// Eight bytes at `start`, inside a table checked when it was opened
+fn word(bytes: &[u8], start: usize) -> [u8; 8] {
+    let mut word = [0; 8];
+    word.copy_from_slice(&bytes[start..start + 8]);
+    word
+}
@@ -0,0 +1,147 @@
+use crate::game::{Game, COLS_COUNT, PLAYER_ROW, SCROLL_DELAY_MS};
+use crate::qlearning::NUM_ACTIONS;
+use std::collections::VecDeque;
+
+pub const DEFAULT_REPLAY_SECONDS: u64 = 5;
+
+/// A single recorded tick of a run
+#[derive(Clone)]
+pub struct Frame {
+    /// Game state right after the course scrolled
+    pub game: Game,
+    /// Q-values the agent saw when choosing the move that led to this frame
+    pub q_values: Option<[f64; NUM_ACTIONS]>,
+}
+
+/// Recording of the last few seconds of a run, used for post-mortem analysis
+#[derive(Clone)]
+pub struct Replay {
+    frames: VecDeque<Frame>,
+    capacity: usize,
+}
+
+impl Replay {
+    /// Creates a replay holding up to `capacity` frames
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            frames: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Creates a replay covering the given number of seconds of play
+    pub fn with_seconds(seconds: u64) -> Self {
+        Self::new((seconds * 1000 / SCROLL_DELAY_MS).max(1) as usize)
+    }
+
+    /// Records a frame, dropping the oldest one when the replay is full
+    pub fn record(&mut self, game: &Game, q_values: Option<[f64; NUM_ACTIONS]>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(Frame {
+            game: game.clone(),
+            q_values,
+        });
+    }
+
+    /// Forgets all recorded frames
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    /// Drops every frame after the one with the given distance
+    ///
+    /// Used to keep the replay consistent after a rewind.
+    pub fn truncate_to(&mut self, distance: i32) {
+        while self
+            .frames
+            .back()
+            .is_some_and(|frame| frame.game.distance > distance)
+        {
+            self.frames.pop_back();
+        }
+    }
+
+    /// Number of recorded frames
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Returns true if no frames have been recorded
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Gets the frame at the given index (oldest first)
+    pub fn frame(&self, index: usize) -> Option<&Frame> {
+        self.frames.get(index)
+    }
+
+    /// Finds the tick at which the crash became unavoidable
+    ///
+    /// See [`find_point_of_no_return`].
+    pub fn point_of_no_return(&self, moves_per_tick: usize) -> Option<usize> {
+        let games: Vec<&Game> = self.frames.iter().map(|frame| &frame.game).collect();
+        find_point_of_no_return(&games, moves_per_tick)
+    }
+}
+
+impl Default for Replay {
+    fn default() -> Self {
+        Self::with_seconds(DEFAULT_REPLAY_SECONDS)
+    }
+}
+
+/// Finds the first frame from which the final crash could no longer be avoided
+///
+/// `frames` are consecutive ticks ending with the crash. From each frame an
+/// exhaustive search over all move sequences (up to `moves_per_tick` cells per
+/// tick) is run against the course that was actually generated. Returns `None`
+/// if the last frame isn't a crash.
+pub fn find_point_of_no_return(frames: &[&Game], moves_per_tick: usize) -> Option<usize> {
+    let last = frames.last()?;
+    if !last.has_collision() {
+        return None;
+    }
+
+    // Escaping from a frame implies escaping from every earlier frame of the
+    // same run, so the first doomed frame marks the point of no return.
+    (0..frames.len()).find(|&start| !can_survive(frames, start, moves_per_tick))
+}
+
+/// Checks whether some move sequence starting at `frames[start]` survives
+/// every remaining frame
+fn can_survive(frames: &[&Game], start: usize, moves_per_tick: usize) -> bool {
+    let mut reachable = [false; COLS_COUNT];
+    reachable[frames[start].player_x] = true;
+    if is_blocked(frames[start], frames[start].player_x) {
+        return false;
+    }
+
+    for frame in &frames[start + 1..] {
+        let mut next = [false; COLS_COUNT];
+        for x in (0..COLS_COUNT).filter(|&x| reachable[x]) {
+            let lo = x.saturating_sub(moves_per_tick);
+            let hi = (x + moves_per_tick).min(COLS_COUNT - 1);
+            for (nx, slot) in next.iter_mut().enumerate().take(hi + 1).skip(lo) {
+                if !is_blocked(frame, nx) {
+                    *slot = true;
+                }
+            }
+        }
+        if !next.contains(&true) {
+            return false;
+        }
+        reachable = next;
+    }
+
+    true
+}
+
+fn is_blocked(game: &Game, x: usize) -> bool {
+    PLAYER_ROW < game.row_count() && game.get_cell(x, PLAYER_ROW).is_wall()
+}
@@ -1,23 +1,25 @@
-use crate::error::Result;
-use crate::game::{Cell, Game, COLS_COUNT, PLAYER_ROW};
+use crate::challenge::Date;
+use crate::config::ResumeSettings;
+use crate::encoder::{Encoder, StateEncoder, WindowEncoder};
+use crate::error::{GameError, Result};
+use crate::game::Game;
+use crate::hyperparams::{Hyperparams, ScheduleClock};
+use crate::model;
+use crate::policy::Policy;
+use crate::rng::SimRng;
+use crate::stopping::StopReason;
+use crate::storage::{QTable, StorageKind};
 use rand::Rng;
-use std::fs::File;
+use std::collections::HashMap;
+use std::fs;
 use std::io::{Read, Write};
 
 pub const NUM_ACTIONS: usize = 3;
-const VIEW_AHEAD: usize = 3;
-const VIEW_RANGE: i32 = 2;
+/// State count of the default 3×5 window encoder
 pub const STATE_SIZE: usize = 32768; // 2^15
 
-const ALPHA: f64 = 0.2;
-const GAMMA: f64 = 0.95;
-const EPSILON_DECAY: f64 = 0.9995;
-
-const COLLISION_REWARD: f64 = -100.0;
-const SURVIVAL_REWARD: f64 = 1.0;
-
 /// Represents an action the agent can take
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[repr(usize)]
 pub enum Action {
     Left = 0,
@@ -43,62 +45,210 @@ impl Action {
 }
 
 /// Q-learning agent that learns to play the game
-#[repr(C)]
+#[derive(Clone)]
 pub struct Agent {
-    pub q: [[f64; NUM_ACTIONS]; STATE_SIZE],
+    /// Q-values, one row per state of the encoder
+    pub q: QTable,
     pub epsilon: f64,
     pub best_score: i32,
     pub episodes: u64,
+    /// Current learning rate
+    pub alpha: f64,
+    pub params: Hyperparams,
+    /// Progress of the epsilon and learning rate schedules
+    pub clock: ScheduleClock,
+    /// Second Q-table, only present when training with Double Q-learning
+    pub q2: Option<QTable>,
+    /// Maps games to states; the Q-table is sized from it
+    pub encoder: Encoder,
+    /// How often each state was trained on, when tracked
+    pub visits: Option<HashMap<usize, u64>>,
+    /// Source of exploration and other random choices during training
+    pub rng: SimRng,
+    /// Why training ended, once it has
+    pub stop_reason: Option<StopReason>,
 }
 
 impl Agent {
     /// Creates a new agent with default values
     pub fn new() -> Self {
+        Self::with_params(Hyperparams::default())
+    }
+
+    /// Creates a new agent with the given hyperparameters
+    pub fn with_params(params: Hyperparams) -> Self {
+        Self::with_encoder(Encoder::default(), params)
+    }
+
+    /// Creates a new agent using the given state encoder
+    pub fn with_encoder(encoder: Encoder, params: Hyperparams) -> Self {
+        Self::with_storage(encoder, params, StorageKind::Dense)
+    }
+
+    /// Creates a new agent whose Q-table uses the given storage
+    pub fn with_storage(encoder: Encoder, params: Hyperparams, storage: StorageKind) -> Self {
+        let clock = ScheduleClock::default();
         Agent {
-            q: [[0.0; NUM_ACTIONS]; STATE_SIZE],
-            epsilon: 1.0,
+            q: QTable::new(storage, encoder.state_count()),
+            epsilon: params.epsilon.value_at(clock),
             best_score: 0,
             episodes: 0,
+            alpha: params.alpha.value_at(clock),
+            params,
+            clock,
+            q2: None,
+            encoder,
+            visits: None,
+            rng: SimRng::from_entropy(),
+            stop_reason: None,
         }
     }
 
-    /// Decays the epsilon value for exploration vs exploitation
-    pub fn decay_epsilon(&mut self) {
-        self.epsilon = (self.epsilon * EPSILON_DECAY).max(0.0);
+    /// Reseeds the agent's random number generator
+    ///
+    /// Training with the same seed, hyperparameters and courses gives
+    /// bit-identical Q-tables.
+    pub fn seed(&mut self, seed: u64) {
+        self.rng = SimRng::new(seed);
     }
 
-    /// Saves the agent's Q-table to a file
-    pub fn save(&self, filename: &str) -> Result<()> {
-        let mut file = File::create(filename)?;
+    /// Encodes the game with the agent's encoder
+    pub fn state(&self, game: &Game) -> usize {
+        self.encoder.encode(game)
+    }
 
-        for state in 0..STATE_SIZE {
-            for action in 0..NUM_ACTIONS {
-                write_f64(&mut file, self.q[state][action])?;
+    /// Gets the Q-values the agent acts on for a state
+    ///
+    /// With Double Q-learning this is the average of both tables.
+    pub fn q_values(&self, state: usize) -> [f64; NUM_ACTIONS] {
+        match self.q2 {
+            Some(ref q2) => {
+                let mut values = self.q[state];
+                for (value, other) in values.iter_mut().zip(q2[state]) {
+                    *value = (*value + other) / 2.0;
+                }
+                values
             }
+            None => self.q[state],
         }
+    }
+
+    /// Advances the schedules by one episode
+    ///
+    /// Called at the end of every episode. Schedules that decay per step are
+    /// advanced by [`Agent::advance_step`] instead.
+    pub fn decay_epsilon(&mut self) {
+        self.clock.episodes += 1;
+        self.refresh_schedules();
+    }
+
+    /// Advances the schedules by one step
+    pub fn advance_step(&mut self) {
+        self.clock.steps += 1;
+        self.refresh_schedules();
+    }
 
-        write_f64(&mut file, self.epsilon)?;
-        write_i32(&mut file, self.best_score)?;
-        write_u64(&mut file, self.episodes)?;
+    pub(crate) fn refresh_schedules(&mut self) {
+        self.epsilon = self.params.epsilon.value_at(self.clock);
+        self.alpha = self.params.alpha.value_at(self.clock);
+    }
 
-        Ok(())
+    /// Saves the agent's Q-table to a file
+    ///
+    /// The file is replaced in one step, so readers never see it half written.
+    pub fn save(&self, filename: &str) -> Result<()> {
+        model::replace_file(filename, |writer| self.save_to(writer))
+    }
+
+    /// Writes the agent to any writer
+    ///
+    /// Writes the versioned format described in [`crate::model`], including
+    /// the encoder, hyperparameters and today's date. With Double Q-learning
+    /// the averaged table is saved.
+    pub fn save_to(&self, writer: impl Write) -> Result<()> {
+        model::write_model(self, Some(Date::today()), writer)
     }
 
     /// Loads the agent's Q-table from a file
     pub fn load(filename: &str) -> Result<Self> {
-        let mut file = File::open(filename)?;
-        let mut agent = Agent::new();
+        Self::load_bytes(&fs::read(filename)?)
+    }
 
-        for state in 0..STATE_SIZE {
-            for action in 0..NUM_ACTIONS {
-                agent.q[state][action] = read_f64(&mut file)?;
-            }
+    /// Reads an agent from any reader
+    ///
+    /// Reads every format version; headerless data was written for the
+    /// default 3×5 window encoder. The table keeps the storage it was saved
+    /// with.
+    pub fn load_from(mut reader: impl Read) -> Result<Self> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        Self::load_bytes(&bytes)
+    }
+
+    fn load_bytes(bytes: &[u8]) -> Result<Self> {
+        model::read_model(bytes).map(|(agent, _)| agent)
+    }
+
+    /// Saves the agent mid-training along with the trainer's course generator
+    /// and the run's settings
+    ///
+    /// Writes to a temporary file first, so an interrupted save leaves the
+    /// previous checkpoint intact.
+    pub fn save_checkpoint(
+        &self,
+        filename: &str,
+        courses: &SimRng,
+        settings: &ResumeSettings,
+    ) -> Result<()> {
+        model::replace_file(filename, |writer| {
+            model::write_checkpoint(self, courses, settings, writer)
+        })
+    }
+
+    /// Loads an agent and course generator saved by [`Agent::save_checkpoint`]
+    pub fn load_checkpoint(filename: &str) -> Result<(Self, SimRng)> {
+        model::read_checkpoint(&fs::read(filename)?)
+    }
+
+    /// Starts counting visits per state
+    pub fn track_visits(&mut self) {
+        self.visits.get_or_insert_with(HashMap::new);
+    }
+
+    /// Counts a visit to a state if visits are tracked
+    pub fn record_visit(&mut self, state: usize) {
+        if let Some(ref mut visits) = self.visits {
+            *visits.entry(state).or_insert(0) += 1;
         }
+    }
 
-        agent.epsilon = read_f64(&mut file)?;
-        agent.best_score = read_i32(&mut file)?;
-        agent.episodes = read_u64(&mut file)?;
+    /// Number of visits to a state, if visits are tracked
+    pub fn visit_count(&self, state: usize) -> Option<u64> {
+        self.visits
+            .as_ref()
+            .map(|visits| visits.get(&state).copied().unwrap_or(0))
+    }
 
+    /// States holding values in either table, in ascending order
+    pub fn stored_states(&self) -> Vec<usize> {
+        let mut states = self.q.stored_states();
+        if let Some(ref q2) = self.q2 {
+            states.extend(q2.stored_states());
+            states.sort_unstable();
+            states.dedup();
+        }
+        states
+    }
+
+    /// Loads a model, failing if it was trained with a different encoder
+    pub fn load_with_encoder(filename: &str, encoder: &Encoder) -> Result<Self> {
+        let agent = Self::load(filename)?;
+        if agent.encoder != *encoder {
+            return Err(GameError::InvalidAgentData(format!(
+                "model uses encoder {} but {} was requested",
+                agent.encoder, encoder
+            )));
+        }
         Ok(agent)
     }
 }
@@ -111,42 +261,19 @@ impl Default for Agent {
 
 /// Encodes the current game state into a numeric representation
 ///
-/// The state is encoded as a bit pattern representing walls in the player's view
+/// The state is encoded as a bit pattern representing walls in the player's
+/// view, using the default 3×5 [`WindowEncoder`].
 pub fn get_state(game: &Game) -> usize {
-    let mut state = 0;
-    let mut bit = 0;
-
-    for row in 0..VIEW_AHEAD {
-        let y = PLAYER_ROW as i32 - 1 - row as i32;
-
-        for dx in -VIEW_RANGE..=VIEW_RANGE {
-            let x = game.player_x as i32 + dx;
-
-            if is_wall_at(game, x, y) {
-                state |= 1 << bit;
-            }
-            bit += 1;
-        }
-    }
-
-    state
+    WindowEncoder::default().encode(game)
 }
 
-/// Checks if there's a wall at the given coordinates
-fn is_wall_at(game: &Game, x: i32, y: i32) -> bool {
-    if y < 0 || x < 0 || x >= COLS_COUNT as i32 {
-        return false;
-    }
-    game.get_cell(x as usize, y as usize) == Cell::Wall
-}
-
-/// Returns the best action for the given state based on Q-values
-pub fn get_best_action(agent: &Agent, state: usize) -> Action {
+/// Returns the action with the highest of the given Q-values
+pub fn best_action(q_values: &[f64; NUM_ACTIONS]) -> Action {
     Action::all()
         .iter()
         .max_by(|&&a, &&b| {
-            agent.q[state][a as usize]
-                .partial_cmp(&agent.q[state][b as usize])
+            q_values[a as usize]
+                .partial_cmp(&q_values[b as usize])
                 .unwrap_or(std::cmp::Ordering::Equal)
         })
         .copied()
@@ -154,13 +281,14 @@ pub fn get_best_action(agent: &Agent, state: usize) -> Action {
 }
 
 /// Chooses an action using epsilon-greedy strategy
-pub fn choose_action(agent: &Agent, state: usize) -> Action {
-    let mut rng = rand::rng();
-    if rng.random::<f64>() < agent.epsilon {
-        let action_idx = rng.random_range(0..NUM_ACTIONS);
+///
+/// Greedy choices come from [`Policy::best_action`], like the game's AI player.
+pub fn choose_action(agent: &mut Agent, state: usize) -> Action {
+    if agent.rng.random::<f64>() < agent.epsilon {
+        let action_idx = agent.rng.random_range(0..NUM_ACTIONS);
         Action::from_usize(action_idx).unwrap_or(Action::Stay)
     } else {
-        get_best_action(agent, state)
+        agent.best_action(state)
     }
 }
 
@@ -179,13 +307,9 @@ pub fn do_action(game: &mut Game, action: Action) {
     }
 }
 
-/// Calculates the reward for the current game state
+/// Calculates the reward for the current game state using the default rewards
 pub fn get_reward(game: &Game) -> f64 {
-    if game.has_collision() {
-        COLLISION_REWARD
-    } else {
-        SURVIVAL_REWARD
-    }
+    Hyperparams::default().reward(game)
 }
 
 /// Updates the Q-value using the Q-learning algorithm
@@ -198,39 +322,6 @@ pub fn update_q(agent: &mut Agent, state: usize, action: Action, reward: f64, ne
 
     let action_idx = action as usize;
     let current_q = agent.q[state][action_idx];
-    let target = reward + GAMMA * max_next_q;
-    agent.q[state][action_idx] = current_q + ALPHA * (target - current_q);
-}
-
-fn read_f64(file: &mut File) -> Result<f64> {
-    let mut buf = [0u8; 8];
-    file.read_exact(&mut buf)?;
-    Ok(f64::from_le_bytes(buf))
-}
-
-fn read_i32(file: &mut File) -> Result<i32> {
-    let mut buf = [0u8; 4];
-    file.read_exact(&mut buf)?;
-    Ok(i32::from_le_bytes(buf))
-}
-
-fn read_u64(file: &mut File) -> Result<u64> {
-    let mut buf = [0u8; 8];
-    file.read_exact(&mut buf)?;
-    Ok(u64::from_le_bytes(buf))
-}
-
-fn write_f64(file: &mut File, value: f64) -> Result<()> {
-    file.write_all(&value.to_le_bytes())?;
-    Ok(())
-}
-
-fn write_i32(file: &mut File, value: i32) -> Result<()> {
-    file.write_all(&value.to_le_bytes())?;
-    Ok(())
-}
-
-fn write_u64(file: &mut File, value: u64) -> Result<()> {
-    file.write_all(&value.to_le_bytes())?;
-    Ok(())
+    let target = reward + agent.params.gamma * max_next_q;
+    agent.q[state][action_idx] = current_q + agent.alpha * (target - current_q);
 }
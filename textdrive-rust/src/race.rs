@@ -0,0 +1,33 @@
+/// Player furthest ahead and their lead over the runner-up
+///
+/// Cars scroll in lockstep, so only a crash opens a gap. Returns `None` while
+/// the front runners are level, including when there's a single player.
+pub fn leader(distances: &[i32]) -> Option<(usize, i32)> {
+    let (first, &best) = distances
+        .iter()
+        .enumerate()
+        .max_by_key(|&(i, &distance)| (distance, std::cmp::Reverse(i)))?;
+    let runner_up = distances
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| i != first)
+        .map(|(_, &distance)| distance)
+        .max()?;
+    (best > runner_up).then_some((first, best - runner_up))
+}
+
+/// Players who win a round that ended with these distances
+///
+/// Everyone who got furthest wins; if all cars got equally far the round is
+/// a draw and nobody does.
+pub fn round_winners(distances: &[i32]) -> Vec<usize> {
+    let Some(&best) = distances.iter().max() else {
+        return Vec::new();
+    };
+    if distances.iter().all(|&distance| distance == best) {
+        return Vec::new();
+    }
+    (0..distances.len())
+        .filter(|&i| distances[i] == best)
+        .collect()
+}
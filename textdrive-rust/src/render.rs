@@ -0,0 +1,75 @@
+use crate::game::{Cell, Game, COLS_COUNT, PLAYER_ROW, ROWS_COUNT};
+use pancurses::{initscr, noecho, Window};
+
+/// Rows above the game field, left for a header
+const DISPLAY_OFFSET: i32 = 2;
+/// Terminal columns per game column
+const CELL_WIDTH: i32 = 2;
+/// Sparkline bars, lowest to highest
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Terminal columns the game field takes
+pub const FIELD_WIDTH: i32 = COLS_COUNT as i32 * CELL_WIDTH;
+
+/// Sets up the terminal for non-blocking, unechoed key input
+pub fn init_terminal() -> Window {
+    let window = initscr();
+    pancurses::cbreak();
+    noecho();
+    window.keypad(true);
+    window.nodelay(true);
+    pancurses::curs_set(0);
+    window
+}
+
+/// Draws the game field below the header, at the left edge
+pub fn draw_game_field(window: &Window, game: &Game) {
+    draw_game_field_at(window, game, 0);
+}
+
+/// Draws the game field below the header, `x_offset` columns in
+pub fn draw_game_field_at(window: &Window, game: &Game, x_offset: i32) {
+    for y in 0..ROWS_COUNT {
+        for x in 0..COLS_COUNT {
+            let ch = get_cell_char(game, x, y);
+            let y_pos = y as i32 + DISPLAY_OFFSET;
+            let x_pos = x as i32 * CELL_WIDTH + x_offset;
+            window.mvaddstr(y_pos, x_pos, ch);
+        }
+    }
+}
+
+/// Text of one cell, two columns wide
+pub fn get_cell_char(game: &Game, x: usize, y: usize) -> &'static str {
+    if y == PLAYER_ROW && x == game.player_x {
+        "車"
+    } else if game.get_cell(x, y) == Cell::Wall {
+        "■"
+    } else {
+        "  "
+    }
+}
+
+/// Bar chart of the last `width` values, scaled between their minimum and
+/// maximum
+pub fn sparkline(values: &[f64], width: usize) -> String {
+    let recent = &values[values.len().saturating_sub(width)..];
+    let finite = recent.iter().copied().filter(|v| v.is_finite());
+    let low = finite.clone().fold(f64::INFINITY, f64::min);
+    let high = finite.fold(f64::NEG_INFINITY, f64::max);
+    let range = high - low;
+
+    recent
+        .iter()
+        .map(|&value| {
+            if !value.is_finite() {
+                ' '
+            } else if range > 0.0 {
+                let level = (value - low) / range * (SPARKS.len() - 1) as f64;
+                SPARKS[level.round() as usize]
+            } else {
+                SPARKS[0]
+            }
+        })
+        .collect()
+}
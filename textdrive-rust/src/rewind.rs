@@ -0,0 +1,148 @@
+use crate::game::{Game, SCROLL_DELAY_MS};
+use std::collections::VecDeque;
+
+pub const DEFAULT_REWIND_SECONDS: u64 = 3;
+pub const DEFAULT_REWIND_CHARGES: u32 = 3;
+
+/// Ring buffer of recent game states that lets the player scrub back in time
+///
+/// A rewind is started with [`Rewind::begin`], scrubbed with
+/// [`Rewind::step_back`] / [`Rewind::step_forward`] and either committed or
+/// cancelled. Committing consumes one charge unless the rewind was started as
+/// a free (practice) rewind.
+#[derive(Clone)]
+pub struct Rewind {
+    history: VecDeque<Game>,
+    capacity: usize,
+    charges: u32,
+    cursor: Option<usize>,
+    free: bool,
+}
+
+impl Rewind {
+    /// Creates a rewind buffer holding up to `capacity` frames
+    pub fn new(capacity: usize, charges: u32) -> Self {
+        Self {
+            history: VecDeque::with_capacity(capacity),
+            capacity,
+            charges,
+            cursor: None,
+            free: false,
+        }
+    }
+
+    /// Creates a rewind buffer covering the given number of seconds of play
+    pub fn with_seconds(seconds: u64, charges: u32) -> Self {
+        let frames = (seconds * 1000 / SCROLL_DELAY_MS).max(1) as usize;
+        Self::new(frames, charges)
+    }
+
+    /// Records a frame, dropping the oldest one when the buffer is full
+    pub fn record(&mut self, game: &Game) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.history.len() == self.capacity {
+            self.history.pop_front();
+        }
+        self.history.push_back(game.clone());
+    }
+
+    /// Forgets all recorded frames and restores the given number of charges
+    pub fn reset(&mut self, charges: u32) {
+        self.history.clear();
+        self.charges = charges;
+        self.cursor = None;
+        self.free = false;
+    }
+
+    /// Number of recorded frames
+    pub fn len(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Returns true if no frames have been recorded
+    pub fn is_empty(&self) -> bool {
+        self.history.is_empty()
+    }
+
+    /// Remaining rewind charges
+    pub fn charges(&self) -> u32 {
+        self.charges
+    }
+
+    /// Returns true while a rewind is being scrubbed
+    pub fn is_scrubbing(&self) -> bool {
+        self.cursor.is_some()
+    }
+
+    /// Number of frames between the scrub position and the latest frame
+    pub fn offset(&self) -> usize {
+        self.cursor
+            .map(|cursor| self.history.len() - 1 - cursor)
+            .unwrap_or(0)
+    }
+
+    /// Starts scrubbing at the latest frame
+    ///
+    /// A free rewind doesn't consume a charge when committed. Returns false if
+    /// there is nothing to rewind to or no charges are left.
+    pub fn begin(&mut self, free: bool) -> bool {
+        if self.history.is_empty() || (!free && self.charges == 0) {
+            return false;
+        }
+        self.cursor = Some(self.history.len() - 1);
+        self.free = free;
+        true
+    }
+
+    /// Moves the scrub position one frame further into the past
+    pub fn step_back(&mut self) -> bool {
+        match self.cursor {
+            Some(cursor) if cursor > 0 => {
+                self.cursor = Some(cursor - 1);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Moves the scrub position one frame towards the present
+    pub fn step_forward(&mut self) -> bool {
+        match self.cursor {
+            Some(cursor) if cursor + 1 < self.history.len() => {
+                self.cursor = Some(cursor + 1);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Gets the frame at the current scrub position
+    pub fn preview(&self) -> Option<&Game> {
+        self.cursor.and_then(|cursor| self.history.get(cursor))
+    }
+
+    /// Finishes scrubbing and returns the selected frame
+    ///
+    /// Frames after the selected one are discarded so play continues from it.
+    pub fn commit(&mut self) -> Option<Game> {
+        let cursor = self.cursor.take()?;
+        self.history.truncate(cursor + 1);
+        if !self.free {
+            self.charges = self.charges.saturating_sub(1);
+        }
+        self.history.back().cloned()
+    }
+
+    /// Stops scrubbing without changing the game
+    pub fn cancel(&mut self) {
+        self.cursor = None;
+    }
+}
+
+impl Default for Rewind {
+    fn default() -> Self {
+        Self::with_seconds(DEFAULT_REWIND_SECONDS, DEFAULT_REWIND_CHARGES)
+    }
+}
@@ -0,0 +1,100 @@
+use crate::error::GameError;
+use std::fmt;
+use std::str::FromStr;
+
+/// Why training ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// Every configured episode was run
+    EpisodeLimit,
+    /// An evaluation reached the target score
+    TargetScore,
+    /// Evaluations stopped improving
+    NoImprovement,
+    /// Q-values stopped changing
+    Converged,
+}
+
+impl StopReason {
+    /// Short name used in model metadata
+    pub fn name(self) -> &'static str {
+        match self {
+            StopReason::EpisodeLimit => "episode-limit",
+            StopReason::TargetScore => "target-score",
+            StopReason::NoImprovement => "no-improvement",
+            StopReason::Converged => "converged",
+        }
+    }
+
+    /// Sentence describing the reason
+    pub fn description(self) -> &'static str {
+        match self {
+            StopReason::EpisodeLimit => "all episodes were run",
+            StopReason::TargetScore => "an evaluation reached the target score",
+            StopReason::NoImprovement => "evaluations stopped improving",
+            StopReason::Converged => "Q-values stopped changing",
+        }
+    }
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for StopReason {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [
+            StopReason::EpisodeLimit,
+            StopReason::TargetScore,
+            StopReason::NoImprovement,
+            StopReason::Converged,
+        ]
+        .into_iter()
+        .find(|reason| reason.name() == s)
+        .ok_or_else(|| GameError::InvalidAgentData(format!("unknown stop reason: {}", s)))
+    }
+}
+
+/// Decides when training can stop before the episode limit
+///
+/// Every criterion is optional; with none set training never stops early.
+#[derive(Debug, Clone, Default)]
+pub struct EarlyStopping {
+    /// Mean evaluation score to stop at
+    pub target_score: Option<f64>,
+    /// Evaluations without a new best mean to stop after
+    pub patience: Option<usize>,
+    /// Q-value change per episode to stop below
+    pub min_q_change: Option<f64>,
+    best: Option<f64>,
+    stale: usize,
+}
+
+impl EarlyStopping {
+    /// Records the mean score of an evaluation
+    pub fn evaluated(&mut self, mean: f64) -> Option<StopReason> {
+        if self.target_score.is_some_and(|target| mean >= target) {
+            return Some(StopReason::TargetScore);
+        }
+        if self.best.is_none_or(|best| mean > best) {
+            self.best = Some(mean);
+            self.stale = 0;
+        } else {
+            self.stale += 1;
+        }
+        self.patience
+            .filter(|&patience| self.stale >= patience)
+            .map(|_| StopReason::NoImprovement)
+    }
+
+    /// Records the Q-value change per episode over the last interval
+    pub fn q_changed(&mut self, change: f64) -> Option<StopReason> {
+        self.min_q_change
+            .filter(|&threshold| change < threshold)
+            .map(|_| StopReason::Converged)
+    }
+}
@@ -0,0 +1,269 @@
+use crate::error::GameError;
+use crate::qlearning::NUM_ACTIONS;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::{Index, IndexMut};
+use std::str::FromStr;
+
+/// Q-values of one state
+pub type QRow = [f64; NUM_ACTIONS];
+
+/// Unvisited states of a sparse table read as zeros
+const ZERO_ROW: QRow = [0.0; NUM_ACTIONS];
+
+/// Most states a dense table may cover, about 400 MB of Q-values
+pub const MAX_DENSE_STATES: usize = 1 << 24;
+
+/// Backing store of a Q-table
+pub trait QStorage {
+    /// Number of states the table covers
+    fn len(&self) -> usize;
+
+    /// Number of states that actually hold values in memory
+    fn stored(&self) -> usize;
+
+    /// Gets the Q-values of a state
+    fn row(&self, state: usize) -> &QRow;
+
+    /// Gets the Q-values of a state for writing
+    fn row_mut(&mut self, state: usize) -> &mut QRow;
+
+    /// Returns true if the table covers no states
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Every state in one heap allocation
+#[derive(Debug, Clone, PartialEq)]
+pub struct DenseStorage {
+    rows: Box<[QRow]>,
+}
+
+impl DenseStorage {
+    /// Creates a zeroed table for `states` states
+    pub fn new(states: usize) -> Self {
+        Self {
+            rows: vec![ZERO_ROW; states].into_boxed_slice(),
+        }
+    }
+}
+
+impl QStorage for DenseStorage {
+    fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    fn stored(&self) -> usize {
+        self.rows.len()
+    }
+
+    fn row(&self, state: usize) -> &QRow {
+        &self.rows[state]
+    }
+
+    fn row_mut(&mut self, state: usize) -> &mut QRow {
+        &mut self.rows[state]
+    }
+}
+
+/// Only states that have been written, for huge or mostly unreachable
+/// state spaces
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseStorage {
+    states: usize,
+    rows: HashMap<usize, QRow>,
+}
+
+impl SparseStorage {
+    /// Creates an empty table for `states` states
+    pub fn new(states: usize) -> Self {
+        Self {
+            states,
+            rows: HashMap::new(),
+        }
+    }
+}
+
+impl QStorage for SparseStorage {
+    fn len(&self) -> usize {
+        self.states
+    }
+
+    fn stored(&self) -> usize {
+        self.rows.len()
+    }
+
+    fn row(&self, state: usize) -> &QRow {
+        assert!(state < self.states, "state {} out of range", state);
+        self.rows.get(&state).unwrap_or(&ZERO_ROW)
+    }
+
+    fn row_mut(&mut self, state: usize) -> &mut QRow {
+        assert!(state < self.states, "state {} out of range", state);
+        self.rows.entry(state).or_insert(ZERO_ROW)
+    }
+}
+
+/// Which storage a Q-table uses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StorageKind {
+    #[default]
+    Dense,
+    Sparse,
+}
+
+impl StorageKind {
+    /// Short name used on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            StorageKind::Dense => "dense",
+            StorageKind::Sparse => "sparse",
+        }
+    }
+
+    /// Checks that a table of this kind can be allocated for `states` states
+    pub fn check_states(self, states: usize) -> Result<(), GameError> {
+        if self == StorageKind::Dense && states > MAX_DENSE_STATES {
+            return Err(GameError::InvalidConfig(format!(
+                "{} states are too many for dense storage (at most {}), use sparse",
+                states, MAX_DENSE_STATES
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for StorageKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for StorageKind {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [StorageKind::Dense, StorageKind::Sparse]
+            .into_iter()
+            .find(|kind| kind.name() == s)
+            .ok_or_else(|| GameError::InvalidConfig(format!("unknown storage: {}", s)))
+    }
+}
+
+/// Q-table with dense or sparse storage
+///
+/// Indexing by state works the same for both, so `table[state][action]`
+/// reads and writes Q-values. Writing to an unvisited state of a sparse
+/// table allocates it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum QTable {
+    Dense(DenseStorage),
+    Sparse(SparseStorage),
+}
+
+impl QTable {
+    /// Creates a zeroed table for `states` states
+    pub fn new(kind: StorageKind, states: usize) -> Self {
+        match kind {
+            StorageKind::Dense => QTable::Dense(DenseStorage::new(states)),
+            StorageKind::Sparse => QTable::Sparse(SparseStorage::new(states)),
+        }
+    }
+
+    /// Number of states the table covers
+    pub fn len(&self) -> usize {
+        self.storage().len()
+    }
+
+    /// Returns true if the table covers no states
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of states that actually hold values in memory
+    pub fn stored(&self) -> usize {
+        self.storage().stored()
+    }
+
+    /// Which storage the table uses
+    pub fn kind(&self) -> StorageKind {
+        match self {
+            QTable::Dense(_) => StorageKind::Dense,
+            QTable::Sparse(_) => StorageKind::Sparse,
+        }
+    }
+
+    /// States that hold values, in ascending order
+    ///
+    /// Every state for a dense table; only written ones for a sparse table.
+    pub fn stored_states(&self) -> Vec<usize> {
+        match self {
+            QTable::Dense(storage) => (0..storage.len()).collect(),
+            QTable::Sparse(storage) => {
+                let mut states: Vec<usize> = storage.rows.keys().copied().collect();
+                states.sort_unstable();
+                states
+            }
+        }
+    }
+
+    /// Largest difference between any Q-value of two tables
+    pub fn max_abs_difference(&self, other: &QTable) -> f64 {
+        let mut states = self.stored_states();
+        states.extend(other.stored_states());
+        states.sort_unstable();
+        states.dedup();
+        states
+            .into_iter()
+            .flat_map(|state| self[state].into_iter().zip(other[state]))
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0, f64::max)
+    }
+
+    fn storage(&self) -> &dyn QStorage {
+        match self {
+            QTable::Dense(storage) => storage,
+            QTable::Sparse(storage) => storage,
+        }
+    }
+
+    fn storage_mut(&mut self) -> &mut dyn QStorage {
+        match self {
+            QTable::Dense(storage) => storage,
+            QTable::Sparse(storage) => storage,
+        }
+    }
+}
+
+impl QStorage for QTable {
+    fn len(&self) -> usize {
+        self.storage().len()
+    }
+
+    fn stored(&self) -> usize {
+        self.storage().stored()
+    }
+
+    fn row(&self, state: usize) -> &QRow {
+        self.storage().row(state)
+    }
+
+    fn row_mut(&mut self, state: usize) -> &mut QRow {
+        self.storage_mut().row_mut(state)
+    }
+}
+
+impl Index<usize> for QTable {
+    type Output = QRow;
+
+    fn index(&self, state: usize) -> &QRow {
+        self.row(state)
+    }
+}
+
+impl IndexMut<usize> for QTable {
+    fn index_mut(&mut self, state: usize) -> &mut QRow {
+        self.row_mut(state)
+    }
+}
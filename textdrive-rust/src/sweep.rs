@@ -0,0 +1,449 @@
+use crate::encoder::Encoder;
+use crate::error::{GameError, Result};
+use crate::evaluation::Evaluation;
+use crate::hyperparams::{Hyperparams, Schedule};
+use crate::rng::SimRng;
+use rand::Rng;
+use std::fmt;
+use std::str::FromStr;
+use std::thread;
+
+/// A setting that can be swept
+pub trait SweepValue: Sized + Clone {
+    /// Parses one listed value
+    fn parse_value(key: &str, text: &str) -> Result<Self>;
+
+    /// Value for a number drawn from a range, if the setting is numeric
+    fn from_number(number: f64) -> Option<Self>;
+}
+
+impl SweepValue for f64 {
+    fn parse_value(key: &str, text: &str) -> Result<Self> {
+        text.parse()
+            .map_err(|_| GameError::InvalidConfig(format!("invalid {}: {}", key, text)))
+    }
+
+    fn from_number(number: f64) -> Option<Self> {
+        Some(number)
+    }
+}
+
+impl SweepValue for Schedule {
+    /// A schedule, or a number for a constant one
+    fn parse_value(_key: &str, text: &str) -> Result<Self> {
+        match text.parse() {
+            Ok(number) => Ok(Schedule::constant(number)),
+            Err(_) => text.parse(),
+        }
+    }
+
+    /// Numbers are constant schedules
+    fn from_number(number: f64) -> Option<Self> {
+        Some(Schedule::constant(number))
+    }
+}
+
+impl SweepValue for Encoder {
+    fn parse_value(_key: &str, text: &str) -> Result<Self> {
+        text.parse()
+    }
+
+    fn from_number(_number: f64) -> Option<Self> {
+        None
+    }
+}
+
+/// Values a setting takes in a sweep
+#[derive(Debug, Clone, PartialEq)]
+pub enum Axis<T> {
+    /// Listed values, written comma-separated
+    List(Vec<T>),
+    /// Numbers between two bounds, written `low..high`
+    Range(f64, f64),
+}
+
+impl<T: SweepValue> Axis<T> {
+    /// Parses a comma-separated list or a `low..high` range
+    pub fn parse(key: &str, text: &str) -> Result<Self> {
+        if let Some((low, high)) = text.split_once("..") {
+            let low = f64::parse_value(key, low)?;
+            let high = f64::parse_value(key, high)?;
+            if T::from_number(low).is_none() {
+                return Err(GameError::InvalidConfig(format!(
+                    "{} can't be swept over a range",
+                    key
+                )));
+            }
+            if low > high {
+                return Err(GameError::InvalidConfig(format!(
+                    "{} range is empty: {}",
+                    key, text
+                )));
+            }
+            return Ok(Axis::Range(low, high));
+        }
+        let values = text
+            .split(',')
+            .map(|value| T::parse_value(key, value.trim()))
+            .collect::<Result<Vec<T>>>()?;
+        Ok(Axis::List(values))
+    }
+
+    /// Values a grid search tries, `points` evenly spaced ones for a range
+    pub fn grid(&self, points: usize) -> Vec<T> {
+        match *self {
+            Axis::List(ref values) => values.clone(),
+            Axis::Range(low, high) => {
+                let step = if points > 1 {
+                    (high - low) / (points - 1) as f64
+                } else {
+                    0.0
+                };
+                (0..points.max(1))
+                    .filter_map(|i| T::from_number(low + step * i as f64))
+                    .collect()
+            }
+        }
+    }
+
+    /// A value picked at random, uniformly within a range
+    pub fn sample(&self, rng: &mut SimRng) -> T {
+        match *self {
+            Axis::List(ref values) => values[rng.random_range(0..values.len())].clone(),
+            Axis::Range(low, high) => {
+                let number = low + rng.random::<f64>() * (high - low);
+                T::from_number(number).expect("range of a numeric setting")
+            }
+        }
+    }
+}
+
+/// How a sweep picks configurations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Search {
+    /// Every combination of the swept values
+    #[default]
+    Grid,
+    /// A fixed number of configurations drawn at random
+    Random,
+}
+
+impl Search {
+    /// Short name used on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            Search::Grid => "grid",
+            Search::Random => "random",
+        }
+    }
+}
+
+impl fmt::Display for Search {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Search {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        [Search::Grid, Search::Random]
+            .into_iter()
+            .find(|search| search.name() == s)
+            .ok_or_else(|| GameError::InvalidConfig(format!("unknown search: {}", s)))
+    }
+}
+
+/// Settings of a sweep, besides those of the runs it trains
+#[derive(Debug, Clone, PartialEq)]
+pub struct SweepConfig {
+    pub space: SweepSpace,
+    pub search: Search,
+    /// Configurations a random search draws
+    pub trials: usize,
+    /// Points a grid search tries across each range
+    pub grid_points: usize,
+    /// Runs per configuration, each with its own seed
+    pub seeds: usize,
+    /// Runs trained at once
+    pub jobs: usize,
+}
+
+impl Default for SweepConfig {
+    fn default() -> Self {
+        Self {
+            space: SweepSpace::default(),
+            search: Search::Grid,
+            trials: 20,
+            grid_points: 3,
+            seeds: 3,
+            jobs: thread::available_parallelism().map_or(1, |n| n.get()),
+        }
+    }
+}
+
+impl SweepConfig {
+    /// Keys of the sweep's own settings, swept ones included
+    pub const KEYS: [&'static str; 11] = [
+        "alpha",
+        "gamma",
+        "epsilon",
+        "collision-reward",
+        "survival-reward",
+        "encoder",
+        "search",
+        "trials",
+        "grid-points",
+        "seeds",
+        "jobs",
+    ];
+
+    /// Sets one setting from its text form
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        match key {
+            "search" => self.search = value.parse()?,
+            "trials" => self.trials = parse_count(key, value)?,
+            "grid-points" => self.grid_points = parse_count(key, value)?,
+            "seeds" => self.seeds = parse_count(key, value)?,
+            "jobs" => self.jobs = parse_count(key, value)?,
+            _ => self.space.set(key, value)?,
+        }
+        Ok(())
+    }
+
+    /// Configurations to train, each varying `base` by the swept settings
+    pub fn trials(&self, base: &Trial, rng: &mut SimRng) -> Vec<Trial> {
+        match self.search {
+            Search::Grid => self.space.grid(base, self.grid_points),
+            Search::Random => self.space.random(base, self.trials, rng),
+        }
+    }
+}
+
+fn parse_count(key: &str, value: &str) -> Result<usize> {
+    match value.parse() {
+        Ok(count) if count > 0 => Ok(count),
+        _ => Err(GameError::InvalidConfig(format!(
+            "{} must be a positive whole number: {}",
+            key, value
+        ))),
+    }
+}
+
+/// One configuration a sweep trains
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trial {
+    pub params: Hyperparams,
+    pub encoder: Encoder,
+    /// The swept settings, as `key=value` pairs
+    pub label: String,
+}
+
+/// Settings a sweep varies; the others keep the base configuration's values
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SweepSpace {
+    pub alpha: Option<Axis<Schedule>>,
+    pub gamma: Option<Axis<f64>>,
+    pub epsilon: Option<Axis<Schedule>>,
+    pub collision_reward: Option<Axis<f64>>,
+    pub survival_reward: Option<Axis<f64>>,
+    pub encoder: Option<Axis<Encoder>>,
+}
+
+impl SweepSpace {
+    /// Sets the values of one swept setting
+    pub fn set(&mut self, key: &str, text: &str) -> Result<()> {
+        match key {
+            "alpha" => self.alpha = Some(Axis::parse(key, text)?),
+            "gamma" => self.gamma = Some(Axis::parse(key, text)?),
+            "epsilon" => self.epsilon = Some(Axis::parse(key, text)?),
+            "collision-reward" => self.collision_reward = Some(Axis::parse(key, text)?),
+            "survival-reward" => self.survival_reward = Some(Axis::parse(key, text)?),
+            "encoder" => self.encoder = Some(Axis::parse(key, text)?),
+            _ => return Err(GameError::InvalidConfig(format!("{} can't be swept", key))),
+        }
+        Ok(())
+    }
+
+    /// Every combination of the swept values, ranges split into `points`
+    pub fn grid(&self, base: &Trial, points: usize) -> Vec<Trial> {
+        let axes = [
+            grid_settings(&self.alpha, points, Setting::Alpha),
+            grid_settings(&self.gamma, points, Setting::Gamma),
+            grid_settings(&self.epsilon, points, Setting::Epsilon),
+            grid_settings(&self.collision_reward, points, Setting::CollisionReward),
+            grid_settings(&self.survival_reward, points, Setting::SurvivalReward),
+            grid_settings(&self.encoder, points, Setting::Encoder),
+        ];
+
+        let mut trials = vec![base.clone()];
+        for values in axes.into_iter().flatten() {
+            trials = trials
+                .iter()
+                .flat_map(|trial| values.iter().map(|value| value.applied_to(trial)))
+                .collect();
+        }
+        trials
+    }
+
+    /// `count` configurations with every swept setting drawn at random
+    pub fn random(&self, base: &Trial, count: usize, rng: &mut SimRng) -> Vec<Trial> {
+        (0..count)
+            .map(|_| {
+                let settings = [
+                    self.alpha
+                        .as_ref()
+                        .map(|axis| Setting::Alpha(axis.sample(rng))),
+                    self.gamma
+                        .as_ref()
+                        .map(|axis| Setting::Gamma(axis.sample(rng))),
+                    self.epsilon
+                        .as_ref()
+                        .map(|axis| Setting::Epsilon(axis.sample(rng))),
+                    self.collision_reward
+                        .as_ref()
+                        .map(|axis| Setting::CollisionReward(axis.sample(rng))),
+                    self.survival_reward
+                        .as_ref()
+                        .map(|axis| Setting::SurvivalReward(axis.sample(rng))),
+                    self.encoder
+                        .as_ref()
+                        .map(|axis| Setting::Encoder(axis.sample(rng))),
+                ];
+                settings
+                    .iter()
+                    .flatten()
+                    .fold(base.clone(), |trial, setting| setting.applied_to(&trial))
+            })
+            .collect()
+    }
+}
+
+/// Grid values of an axis, if the setting is swept
+fn grid_settings<T: SweepValue>(
+    axis: &Option<Axis<T>>,
+    points: usize,
+    setting: fn(T) -> Setting,
+) -> Option<Vec<Setting>> {
+    axis.as_ref()
+        .map(|axis| axis.grid(points).into_iter().map(setting).collect())
+}
+
+/// One value of a swept setting
+#[derive(Debug, Clone, PartialEq)]
+enum Setting {
+    Alpha(Schedule),
+    Gamma(f64),
+    Epsilon(Schedule),
+    CollisionReward(f64),
+    SurvivalReward(f64),
+    Encoder(Encoder),
+}
+
+impl Setting {
+    /// Copy of `trial` with this value set and added to its label
+    fn applied_to(&self, trial: &Trial) -> Trial {
+        let mut trial = trial.clone();
+        let (key, value) = match *self {
+            Setting::Alpha(alpha) => {
+                trial.params.alpha = alpha;
+                ("alpha", format_schedule(alpha))
+            }
+            Setting::Gamma(gamma) => {
+                trial.params.gamma = gamma;
+                ("gamma", format_number(gamma))
+            }
+            Setting::Epsilon(epsilon) => {
+                trial.params.epsilon = epsilon;
+                ("epsilon", format_schedule(epsilon))
+            }
+            Setting::CollisionReward(reward) => {
+                trial.params.collision_reward = reward;
+                ("collision-reward", format_number(reward))
+            }
+            Setting::SurvivalReward(reward) => {
+                trial.params.survival_reward = reward;
+                ("survival-reward", format_number(reward))
+            }
+            Setting::Encoder(ref encoder) => {
+                trial.encoder = encoder.clone();
+                ("encoder", encoder.to_string())
+            }
+        };
+        if !trial.label.is_empty() {
+            trial.label.push(' ');
+        }
+        trial.label.push_str(&format!("{}={}", key, value));
+        trial
+    }
+}
+
+/// A constant schedule as its number, others in full
+fn format_schedule(schedule: Schedule) -> String {
+    if schedule == Schedule::constant(schedule.start) {
+        format_number(schedule.start)
+    } else {
+        format!("\"{}\"", schedule)
+    }
+}
+
+/// Shortest form of a number, at most four decimals
+fn format_number(value: f64) -> String {
+    let text = format!("{:.4}", value);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Scores of one configuration over every seed it was trained with
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ranking {
+    /// Index of the configuration
+    pub trial: usize,
+    /// Mean evaluation distance of each seed's run
+    pub means: Vec<f64>,
+    /// Crash rate averaged over the runs
+    pub crash_rate: f64,
+}
+
+impl Ranking {
+    /// Mean distance over all runs
+    pub fn mean(&self) -> f64 {
+        self.means.iter().sum::<f64>() / self.means.len().max(1) as f64
+    }
+
+    /// Standard deviation of the runs' means
+    pub fn std_dev(&self) -> f64 {
+        if self.means.len() < 2 {
+            return 0.0;
+        }
+        let mean = self.mean();
+        let variance = self.means.iter().map(|m| (m - mean).powi(2)).sum::<f64>()
+            / (self.means.len() - 1) as f64;
+        variance.sqrt()
+    }
+}
+
+/// Groups run evaluations by configuration, best mean distance first
+pub fn rank(runs: &[(usize, Evaluation)]) -> Vec<Ranking> {
+    let mut rankings: Vec<Ranking> = Vec::new();
+    for (trial, evaluation) in runs {
+        let index = match rankings.iter().position(|r| r.trial == *trial) {
+            Some(index) => index,
+            None => {
+                rankings.push(Ranking {
+                    trial: *trial,
+                    means: Vec::new(),
+                    crash_rate: 0.0,
+                });
+                rankings.len() - 1
+            }
+        };
+        rankings[index].means.push(evaluation.mean());
+        rankings[index].crash_rate += evaluation.crash_rate();
+    }
+    for ranking in &mut rankings {
+        ranking.crash_rate /= ranking.means.len() as f64;
+    }
+    rankings.sort_by(|a, b| b.mean().total_cmp(&a.mean()).then(a.trial.cmp(&b.trial)));
+    rankings
+}
@@ -0,0 +1,138 @@
+use crate::error::Result;
+use crate::metrics::EpisodeMetrics;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Version string TensorBoard expects in the first event of a file
+const FILE_VERSION: &str = "brain.Event:2";
+/// Added to rotated CRCs in record framing
+const CRC_MASK_DELTA: u32 = 0xA282_EAD8;
+/// Reversed Castagnoli polynomial
+const CRC32C_POLY: u32 = 0x82F6_3B78;
+
+/// Writes scalars to a TensorBoard event file
+///
+/// The file is a sequence of TFRecords, each holding an `Event` protocol
+/// buffer. Only the few fields scalars need are encoded, by hand.
+pub struct EventWriter {
+    writer: BufWriter<File>,
+}
+
+impl EventWriter {
+    /// Creates a new event file in `dir`, creating the directory if needed
+    pub fn create(dir: &str) -> Result<Self> {
+        fs::create_dir_all(dir)?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let name = format!(
+            "events.out.tfevents.{}.textdrive.{}",
+            now.as_secs(),
+            std::process::id()
+        );
+        let file = File::create(Path::new(dir).join(name))?;
+
+        let mut writer = Self {
+            writer: BufWriter::new(file),
+        };
+        let mut event = event_header(0);
+        put_bytes(&mut event, 3, FILE_VERSION.as_bytes());
+        writer.write_record(&event)?;
+        Ok(writer)
+    }
+
+    /// Adds a scalar value for a training step
+    pub fn add_scalar(&mut self, tag: &str, step: u64, value: f64) -> Result<()> {
+        let mut value_message = Vec::new();
+        put_bytes(&mut value_message, 1, tag.as_bytes());
+        // simple_value, field 2, is a 32-bit float
+        value_message.push(2 << 3 | 5);
+        value_message.extend_from_slice(&(value as f32).to_le_bytes());
+
+        let mut summary = Vec::new();
+        put_bytes(&mut summary, 1, &value_message);
+
+        let mut event = event_header(step);
+        put_bytes(&mut event, 5, &summary);
+        self.write_record(&event)
+    }
+
+    /// Adds every metric of an episode, tagged with its column name
+    pub fn add_metrics(&mut self, metrics: &EpisodeMetrics) -> Result<()> {
+        let columns = EpisodeMetrics::COLUMNS.iter().zip(metrics.values());
+        for (name, value) in columns.skip(1) {
+            if let Some(value) = value {
+                self.add_scalar(name, metrics.episode, value)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes buffered events through, so TensorBoard sees them
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Frames data as a TFRecord: length, its CRC, data, the data's CRC
+    fn write_record(&mut self, data: &[u8]) -> Result<()> {
+        let len = (data.len() as u64).to_le_bytes();
+        self.writer.write_all(&len)?;
+        self.writer.write_all(&masked_crc32c(&len).to_le_bytes())?;
+        self.writer.write_all(data)?;
+        self.writer.write_all(&masked_crc32c(data).to_le_bytes())?;
+        Ok(())
+    }
+}
+
+/// Wall time (field 1) and step (field 2) of an event
+fn event_header(step: u64) -> Vec<u8> {
+    let wall_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64();
+    let mut event = vec![1 << 3 | 1];
+    event.extend_from_slice(&wall_time.to_le_bytes());
+    event.push(2 << 3);
+    put_varint(&mut event, step);
+    event
+}
+
+/// Appends a length-delimited field
+fn put_bytes(out: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    put_varint(out, field << 3 | 2);
+    put_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// CRC-32C (Castagnoli) checksum
+pub fn crc32c(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                crc >> 1 ^ CRC32C_POLY
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// CRC-32C rotated and offset the way TFRecord framing stores it
+pub fn masked_crc32c(bytes: &[u8]) -> u32 {
+    let crc = crc32c(bytes);
+    crc.rotate_right(15).wrapping_add(CRC_MASK_DELTA)
+}
@@ -0,0 +1,261 @@
+use crate::error::GameError;
+use crate::learner::{update_toward, Learner, Transition};
+use crate::qlearning::{best_action, Action, Agent};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+
+/// Traces smaller than this are dropped to keep the trace table sparse
+const MIN_TRACE: f64 = 1e-4;
+
+/// Buffers the last `n` steps to update with n-step TD targets
+///
+/// The target for the oldest buffered step is the discounted sum of the next
+/// `n` rewards plus the discounted bootstrap value `n` steps later, so a
+/// collision penalty reaches `n` states back in a single update.
+#[derive(Debug, Clone)]
+pub struct NStepBuffer {
+    n: usize,
+    pending: VecDeque<(usize, Action, f64)>,
+}
+
+impl NStepBuffer {
+    /// Creates a buffer for `n`-step returns (`n` is at least 1)
+    pub fn new(n: usize) -> Self {
+        let n = n.max(1);
+        Self {
+            n,
+            pending: VecDeque::with_capacity(n),
+        }
+    }
+
+    /// Number of steps in each return
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// Adds a transition, updating the oldest step once `n` are buffered
+    pub fn push(&mut self, agent: &mut Agent, learner: Learner, t: &Transition) {
+        self.pending.push_back((t.state, t.action, t.reward));
+        if self.pending.len() == self.n {
+            self.update_oldest(agent, learner, t.next_state, t.next_action);
+        }
+    }
+
+    /// Updates every buffered step with a truncated return
+    ///
+    /// Called when the episode ends, with the state and action after the last
+    /// transition.
+    pub fn flush(
+        &mut self,
+        agent: &mut Agent,
+        learner: Learner,
+        next_state: usize,
+        next_action: Action,
+    ) {
+        while !self.pending.is_empty() {
+            self.update_oldest(agent, learner, next_state, next_action);
+        }
+    }
+
+    fn update_oldest(
+        &mut self,
+        agent: &mut Agent,
+        learner: Learner,
+        next_state: usize,
+        next_action: Action,
+    ) {
+        let gamma = agent.params.gamma;
+        let mut target = learner.bootstrap(agent, next_state, next_action);
+        for &(_, _, reward) in self.pending.iter().rev() {
+            target = reward + gamma * target;
+        }
+
+        if let Some((state, action, _)) = self.pending.pop_front() {
+            update_toward(agent, state, action, target);
+        }
+    }
+}
+
+/// Variant of Q(λ)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceKind {
+    /// Watkins's Q(λ): traces are cut after exploratory actions
+    Watkins,
+    /// Peng's Q(λ): traces are never cut, mixing on- and off-policy returns
+    Peng,
+}
+
+impl TraceKind {
+    /// Short name used on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            TraceKind::Watkins => "watkins",
+            TraceKind::Peng => "peng",
+        }
+    }
+}
+
+impl fmt::Display for TraceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for TraceKind {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [TraceKind::Watkins, TraceKind::Peng]
+            .into_iter()
+            .find(|kind| kind.name() == s)
+            .ok_or_else(|| GameError::InvalidConfig(format!("unknown trace kind: {}", s)))
+    }
+}
+
+/// Sparse accumulating eligibility traces for Q(λ)
+///
+/// Only state-action pairs visited recently have a trace, so the table stays
+/// small even though the Q-table has tens of thousands of states.
+#[derive(Debug, Clone)]
+pub struct EligibilityTraces {
+    lambda: f64,
+    kind: TraceKind,
+    traces: HashMap<(usize, Action), f64>,
+}
+
+impl EligibilityTraces {
+    /// Creates an empty trace table
+    pub fn new(lambda: f64, kind: TraceKind) -> Self {
+        Self {
+            lambda,
+            kind,
+            traces: HashMap::new(),
+        }
+    }
+
+    /// Trace decay parameter (λ)
+    pub fn lambda(&self) -> f64 {
+        self.lambda
+    }
+
+    /// Variant of Q(λ) in use
+    pub fn kind(&self) -> TraceKind {
+        self.kind
+    }
+
+    /// Number of state-action pairs with a live trace
+    pub fn len(&self) -> usize {
+        self.traces.len()
+    }
+
+    /// Returns true if no trace is live
+    pub fn is_empty(&self) -> bool {
+        self.traces.is_empty()
+    }
+
+    /// Gets the trace of a state-action pair
+    pub fn get(&self, state: usize, action: Action) -> f64 {
+        self.traces.get(&(state, action)).copied().unwrap_or(0.0)
+    }
+
+    /// Forgets all traces; called at the start of every episode
+    pub fn clear(&mut self) {
+        self.traces.clear();
+    }
+
+    /// Updates the agent's Q-values from one transition
+    pub fn update(&mut self, agent: &mut Agent, t: &Transition) {
+        match self.kind {
+            TraceKind::Watkins => self.update_watkins(agent, t),
+            TraceKind::Peng => self.update_peng(agent, t),
+        }
+    }
+
+    fn update_watkins(&mut self, agent: &mut Agent, t: &Transition) {
+        let next_q = agent.q[t.next_state];
+        let greedy = best_action(&next_q);
+        let max_next_q = next_q[greedy as usize];
+        let delta =
+            t.reward + agent.params.gamma * max_next_q - agent.q[t.state][t.action as usize];
+
+        *self.traces.entry((t.state, t.action)).or_insert(0.0) += 1.0;
+        self.apply(agent, delta);
+
+        // An exploratory next action breaks the greedy chain the traces follow
+        let decay = if t.next_action == greedy || next_q[t.next_action as usize] == max_next_q {
+            agent.params.gamma * self.lambda
+        } else {
+            0.0
+        };
+        self.decay(decay);
+    }
+
+    fn update_peng(&mut self, agent: &mut Agent, t: &Transition) {
+        let gamma = agent.params.gamma;
+        let max_next_q = agent.q[t.next_state]
+            .into_iter()
+            .fold(f64::NEG_INFINITY, f64::max);
+        let max_q = agent.q[t.state]
+            .into_iter()
+            .fold(f64::NEG_INFINITY, f64::max);
+        let current_q = agent.q[t.state][t.action as usize];
+        let delta_current = t.reward + gamma * max_next_q - current_q;
+        let delta = t.reward + gamma * max_next_q - max_q;
+
+        self.decay(gamma * self.lambda);
+        self.apply(agent, delta);
+        agent.q[t.state][t.action as usize] += agent.alpha * delta_current;
+        *self.traces.entry((t.state, t.action)).or_insert(0.0) += 1.0;
+    }
+
+    fn apply(&self, agent: &mut Agent, delta: f64) {
+        let step = agent.alpha * delta;
+        for (&(state, action), &trace) in &self.traces {
+            agent.q[state][action as usize] += step * trace;
+        }
+    }
+
+    fn decay(&mut self, factor: f64) {
+        self.traces.retain(|_, trace| {
+            *trace *= factor;
+            *trace >= MIN_TRACE
+        });
+    }
+}
+
+/// How TD errors are assigned to earlier states
+#[derive(Debug, Clone)]
+pub enum CreditAssignment {
+    /// One-step updates straight from the learner
+    OneStep,
+    /// n-step TD targets
+    NStep(NStepBuffer),
+    /// Q(λ) with eligibility traces
+    Lambda(EligibilityTraces),
+}
+
+impl CreditAssignment {
+    /// Prepares for a new episode
+    pub fn start_episode(&mut self) {
+        if let CreditAssignment::Lambda(ref mut traces) = self {
+            traces.clear();
+        }
+    }
+
+    /// Learns from one transition
+    pub fn update(&mut self, agent: &mut Agent, learner: Learner, t: &Transition) {
+        match self {
+            CreditAssignment::OneStep => learner.update(agent, t),
+            CreditAssignment::NStep(buffer) => buffer.push(agent, learner, t),
+            CreditAssignment::Lambda(traces) => traces.update(agent, t),
+        }
+    }
+
+    /// Finishes the episode after its last transition
+    pub fn finish_episode(&mut self, agent: &mut Agent, learner: Learner, last: &Transition) {
+        if let CreditAssignment::NStep(buffer) = self {
+            buffer.flush(agent, learner, last.next_state, last.next_action);
+        }
+    }
+}
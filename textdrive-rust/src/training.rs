@@ -0,0 +1,96 @@
+use crate::game::Game;
+use crate::learner::{Learner, Transition};
+use crate::metrics::EpisodeMetrics;
+use crate::qlearning::{choose_action, do_action, Agent};
+use crate::rng::SimRng;
+use crate::traces::CreditAssignment;
+use rand::RngCore;
+
+/// Runs an episode on the next course from `courses` and advances the
+/// exploration schedule
+///
+/// The returned metrics carry the epsilon and alpha the episode was run with.
+pub fn train_episode(
+    agent: &mut Agent,
+    learner: Learner,
+    credit: &mut CreditAssignment,
+    courses: &mut SimRng,
+    max_steps: usize,
+) -> EpisodeMetrics {
+    let course = courses.next_u64();
+    let (epsilon, alpha) = (agent.epsilon, agent.alpha);
+    let mut metrics = run_episode(agent, learner, credit, course, max_steps);
+    metrics.epsilon = epsilon;
+    metrics.alpha = alpha;
+    agent.decay_epsilon();
+    metrics
+}
+
+/// Runs one learning episode on the course of `course_seed`
+///
+/// The episode ends on a crash or after `max_steps` steps, updating the
+/// table through `credit` as it goes.
+pub fn run_episode(
+    agent: &mut Agent,
+    learner: Learner,
+    credit: &mut CreditAssignment,
+    course_seed: u64,
+    max_steps: usize,
+) -> EpisodeMetrics {
+    let mut game = Game::with_seed(course_seed);
+    let mut state = agent.state(&game);
+    let mut action = choose_action(agent, state);
+    let mut last = None;
+    let mut metrics = EpisodeMetrics::default();
+    let mut td_error_sum = 0.0;
+
+    credit.start_episode();
+
+    for _step in 0..max_steps {
+        do_action(&mut game, action);
+        game.scroll_course();
+
+        let reward = agent.params.reward(&game);
+        let next_state = agent.state(&game);
+        // Chosen before the update so SARSA can bootstrap from it
+        let next_action = choose_action(agent, next_state);
+
+        let transition = Transition {
+            state,
+            action,
+            reward,
+            next_state,
+            next_action,
+        };
+        let target =
+            reward + agent.params.gamma * learner.bootstrap(agent, next_state, next_action);
+        td_error_sum += (target - agent.q_values(state)[action as usize]).abs();
+        metrics.total_reward += reward;
+        metrics.steps += 1;
+
+        credit.update(agent, learner, &transition);
+        agent.record_visit(state);
+        agent.advance_step();
+        last = Some(transition);
+
+        if game.has_collision() {
+            break;
+        }
+
+        state = next_state;
+        action = next_action;
+    }
+
+    if let Some(ref last) = last {
+        credit.finish_episode(agent, learner, last);
+    }
+
+    if game.distance > agent.best_score {
+        agent.best_score = game.distance;
+    }
+    agent.episodes += 1;
+
+    metrics.distance = game.distance;
+    metrics.mean_abs_td_error = td_error_sum / metrics.steps.max(1) as f64;
+    metrics
+}
@@ -0,0 +1,107 @@
+use crate::encoder::{Encoder, StateEncoder};
+use crate::game::Game;
+use crate::qlearning::{do_action, Action};
+use crate::rng::SimRng;
+use rand::RngCore;
+
+/// Many games stepped together
+///
+/// Each game is a [`Game`], moved, scrolled and checked for collisions just
+/// as when played on its own. A game that crashes starts over on the next
+/// course straight away.
+#[derive(Clone)]
+pub struct VecGame {
+    games: Vec<Game>,
+    /// Seeds of the courses crashed games continue on
+    courses: SimRng,
+}
+
+/// Result of stepping every game once
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StepOutcome {
+    /// Whether each game crashed, and was reset, on this step
+    pub crashed: Vec<bool>,
+    /// Distance each game reached; for a crashed game, the distance it
+    /// crashed at
+    pub distances: Vec<i32>,
+}
+
+impl VecGame {
+    /// Creates `count` games; their courses, and those they continue on after
+    /// crashing, are drawn from `seed`
+    pub fn new(count: usize, seed: u64) -> Self {
+        let mut courses = SimRng::new(seed);
+        let seeds: Vec<u64> = (0..count).map(|_| courses.next_u64()).collect();
+        Self::with_courses(&seeds, courses)
+    }
+
+    /// Creates one game per course seed; crashed games continue on courses
+    /// drawn from the first seed
+    pub fn with_seeds(seeds: &[u64]) -> Self {
+        Self::with_courses(seeds, SimRng::new(seeds.first().copied().unwrap_or(0)))
+    }
+
+    fn with_courses(seeds: &[u64], courses: SimRng) -> Self {
+        Self {
+            games: seeds.iter().map(|&seed| Game::with_seed(seed)).collect(),
+            courses,
+        }
+    }
+
+    /// Number of games
+    pub fn len(&self) -> usize {
+        self.games.len()
+    }
+
+    /// Whether there are no games
+    pub fn is_empty(&self) -> bool {
+        self.games.is_empty()
+    }
+
+    /// Seed of the course a game is on
+    pub fn seed(&self, game: usize) -> u64 {
+        self.games[game].seed()
+    }
+
+    /// Distance a game has reached on its current course
+    pub fn distance(&self, game: usize) -> i32 {
+        self.games[game].distance
+    }
+
+    /// Read access to one game, for encoders and rendering
+    pub fn board(&self, game: usize) -> &Game {
+        &self.games[game]
+    }
+
+    /// Encodes every game into `states`
+    pub fn observe(&self, encoder: &Encoder, states: &mut Vec<usize>) {
+        states.clear();
+        states.extend(self.games.iter().map(|game| encoder.encode(game)));
+    }
+
+    /// Moves every car by its action and scrolls every course one row
+    ///
+    /// Crashed games are reset to the start of the next course.
+    pub fn step(&mut self, actions: &[Action], outcome: &mut StepOutcome) {
+        assert_eq!(actions.len(), self.len(), "one action per game");
+        outcome.crashed.clear();
+        outcome.distances.clear();
+
+        for (game, &action) in self.games.iter_mut().zip(actions) {
+            do_action(game, action);
+            game.scroll_course();
+
+            let crashed = game.has_collision();
+            outcome.crashed.push(crashed);
+            outcome.distances.push(game.distance);
+            if crashed {
+                *game = Game::with_seed(self.courses.next_u64());
+            }
+        }
+    }
+
+    /// Starts a game over on the course of `seed`
+    pub fn reset(&mut self, game: usize, seed: u64) {
+        self.games[game] = Game::with_seed(seed);
+    }
+}
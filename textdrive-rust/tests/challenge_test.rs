@@ -0,0 +1,121 @@
+mod common;
+
+use common::temp_path;
+use textdrive::challenge::*;
+use textdrive::error::GameError;
+
+#[test]
+fn test_date_from_days() {
+    assert_eq!(Date::from_days(0), Date::new(1970, 1, 1).unwrap());
+    assert_eq!(Date::from_days(19_723), Date::new(2024, 1, 1).unwrap());
+    assert_eq!(Date::from_days(-1), Date::new(1969, 12, 31).unwrap());
+}
+
+#[test]
+fn test_date_days_round_trip() {
+    for days in (-1000..40_000).step_by(37) {
+        assert_eq!(Date::from_days(days).to_days(), days);
+    }
+}
+
+#[test]
+fn test_date_new_rejects_invalid() {
+    assert!(Date::new(2023, 2, 29).is_none());
+    assert!(Date::new(2024, 2, 29).is_some());
+    assert!(Date::new(2024, 13, 1).is_none());
+    assert!(Date::new(2024, 4, 31).is_none());
+}
+
+#[test]
+fn test_date_weekday() {
+    // 2024-01-01 was a Monday
+    assert_eq!(Date::new(2024, 1, 1).unwrap().weekday(), 0);
+    assert_eq!(Date::new(1970, 1, 1).unwrap().weekday(), 3);
+}
+
+#[test]
+fn test_date_parse_and_display() {
+    let date: Date = "2026-10-19".parse().unwrap();
+    assert_eq!(date, Date::new(2026, 10, 19).unwrap());
+    assert_eq!(date.to_string(), "2026-10-19");
+    assert!("2026-10".parse::<Date>().is_err());
+}
+
+#[test]
+fn test_daily_seed_differs_per_day() {
+    let day = Date::new(2026, 10, 19).unwrap();
+    let next = Date::from_days(day.to_days() + 1);
+
+    assert_eq!(daily_seed(day), daily_seed(day));
+    assert_ne!(daily_seed(day), daily_seed(next));
+}
+
+#[test]
+fn test_course_code_round_trip() {
+    let code = CourseCode {
+        seed: 0xDEAD_BEEF,
+        scroll_delay_ms: 120,
+    };
+    let text = code.encode();
+
+    assert_eq!(text.len(), 11);
+    assert_eq!(&text[5..6], "-");
+    assert_eq!(CourseCode::decode(&text).unwrap(), code);
+    assert_eq!(
+        CourseCode::decode(&text.to_lowercase().replace('-', "")).unwrap(),
+        code
+    );
+}
+
+#[test]
+fn test_course_code_detects_typo() {
+    let text = CourseCode::new(12345).encode();
+    let mut chars: Vec<char> = text.chars().collect();
+    chars[2] = if chars[2] == 'A' { 'B' } else { 'A' };
+    let typo: String = chars.into_iter().collect();
+
+    match CourseCode::decode(&typo) {
+        Err(GameError::InvalidCourseCode(_)) => {}
+        _ => panic!("Expected GameError::InvalidCourseCode"),
+    }
+}
+
+#[test]
+fn test_course_code_rejects_bad_input() {
+    assert!(CourseCode::decode("").is_err());
+    assert!(CourseCode::decode("UUUUU-UUUUU").is_err());
+    assert!(CourseCode::decode("00000-000000").is_err());
+}
+
+#[test]
+fn test_challenge_history_records_first_attempt() {
+    let date = Date::new(2026, 10, 19).unwrap();
+    let mut history = ChallengeHistory::new();
+
+    assert!(history.record(date, 120));
+    assert!(!history.record(date, 500));
+    assert_eq!(history.get(date), Some(120));
+    assert!(history.has_played(date));
+}
+
+#[test]
+fn test_challenge_history_save_load() {
+    let path = &temp_path("history.txt");
+
+    let mut history = ChallengeHistory::new();
+    history.record(Date::new(2026, 10, 18).unwrap(), 80);
+    history.record(Date::new(2026, 10, 19).unwrap(), 120);
+    history.save(path).unwrap();
+
+    let loaded = ChallengeHistory::load(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    assert_eq!(loaded, history);
+    assert_eq!(loaded.best(), Some((Date::new(2026, 10, 19).unwrap(), 120)));
+}
+
+#[test]
+fn test_challenge_history_load_missing() {
+    let history = ChallengeHistory::load("definitely_missing_history.txt").unwrap();
+    assert_eq!(history.iter().count(), 0);
+}
@@ -0,0 +1,64 @@
+// Each test crate uses only some of these
+#![allow(dead_code)]
+
+use textdrive::encoder::Encoder;
+use textdrive::hyperparams::{Hyperparams, Schedule};
+use textdrive::qlearning::Agent;
+use textdrive::storage::StorageKind;
+
+/// Path in the temp directory unique to this test process; `name` stays at
+/// the end so its extension is kept
+pub fn temp_path(name: &str) -> String {
+    std::env::temp_dir()
+        .join(format!("textdrive_{}_{}", std::process::id(), name))
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Agent with the default encoder and an all-zero table
+pub fn empty_agent(storage: StorageKind) -> Agent {
+    Agent::with_storage(Encoder::default(), Hyperparams::default(), storage)
+}
+
+/// Agent with a few distinct states set and every other state untouched
+pub fn trained_agent(storage: StorageKind) -> Agent {
+    let mut agent = empty_agent(storage);
+    agent.q[0] = [0.0, 0.0, 3.0];
+    agent.q[500] = [-1.0, 2.0, 0.5];
+    agent.q[32767] = [7.0, 0.0, 0.0];
+    agent
+}
+
+/// Agent with different Q-values in every state
+pub fn filled_agent() -> Agent {
+    let mut agent = empty_agent(StorageKind::Dense);
+    for state in 0..agent.q.len() {
+        let x = state as f64;
+        agent.q[state] = [
+            (x * 0.37).sin() * 50.0,
+            (x * 0.11).cos() * 80.0,
+            -x / 1000.0,
+        ];
+    }
+    agent
+}
+
+/// Agent whose encoder, storage, hyperparameters and counters all differ
+/// from the defaults, to check what a model file keeps
+pub fn sample_agent() -> Agent {
+    let params = Hyperparams {
+        epsilon: Schedule::exponential(0.9, 0.99).with_min(0.05),
+        gamma: 0.9,
+        collision_reward: -50.0,
+        ..Default::default()
+    };
+    let encoder: Encoder = "gap".parse().unwrap();
+    let mut agent = Agent::with_storage(encoder, params, StorageKind::Sparse);
+    agent.q[42] = [1.5, -2.0, 0.25];
+    agent.best_score = 321;
+    agent.episodes = 1000;
+    agent.clock.episodes = 1000;
+    agent.clock.steps = 5000;
+    agent.epsilon = 0.3;
+    agent
+}
@@ -0,0 +1,132 @@
+mod common;
+
+use common::temp_path;
+use std::fs;
+use textdrive::config::TrainConfig;
+use textdrive::error::GameError;
+use textdrive::hyperparams::Schedule;
+use textdrive::learner::Learner;
+use textdrive::qlearning::Agent;
+use textdrive::rng::SimRng;
+use textdrive::storage::StorageKind;
+use textdrive::traces::CreditAssignment;
+
+#[test]
+fn test_toml_settings() {
+    let mut config = TrainConfig::default();
+    config
+        .apply_toml(
+            r#"
+            episodes = 2000
+            max-steps = 500
+            output = "runs/sarsa.bin"
+            learner = "sarsa"
+            storage = "sparse"
+            gamma = 0.9
+            alpha = "linear:0.05:1000"
+            collision-reward = -50
+            compress = true
+            tui = true
+            "#,
+        )
+        .unwrap();
+
+    assert_eq!(config.episodes, 2000);
+    assert_eq!(config.max_steps, 500);
+    assert_eq!(config.output, "runs/sarsa.bin");
+    assert_eq!(config.learner, Learner::Sarsa);
+    assert_eq!(config.storage, Some(StorageKind::Sparse));
+    assert!(config.compress);
+    assert!(config.tui);
+    let params = config.params.unwrap();
+    assert_eq!(params.gamma, 0.9);
+    assert_eq!(params.collision_reward, -50.0);
+    assert_eq!(
+        params.alpha,
+        "linear:0.05:1000".parse::<Schedule>().unwrap()
+    );
+    assert_eq!(
+        config.report_interval,
+        TrainConfig::default().report_interval
+    );
+}
+
+#[test]
+fn test_set_overrides_file() {
+    let mut config = TrainConfig::default();
+    config.apply_toml("episodes = 2000\nseed = 7").unwrap();
+    config.set("episodes", "300").unwrap();
+
+    assert_eq!(config.episodes, 300);
+    assert_eq!(config.seed, Some(7));
+}
+
+#[test]
+fn test_invalid_settings() {
+    let mut config = TrainConfig::default();
+
+    assert!(matches!(
+        config.set("episodes", "0"),
+        Err(GameError::InvalidConfig(_))
+    ));
+    assert!(config.set("epochs", "10").is_err());
+    assert!(config.set("gamma", "high").is_err());
+    assert!(config.apply_toml("episodes = [1, 2]").is_err());
+    assert!(config.apply_toml("episodes = ").is_err());
+}
+
+#[test]
+fn test_validate_combinations() {
+    let mut config = TrainConfig::default();
+    config.set("n-step", "3").unwrap();
+    assert!(matches!(config.credit(), CreditAssignment::NStep(_)));
+    config.set("lambda", "0.9").unwrap();
+    assert!(config.validate().is_err());
+
+    let checkpoint = temp_path("validate_checkpoint.bin");
+    let config = TrainConfig::default();
+    Agent::new()
+        .save_checkpoint(&checkpoint, &SimRng::new(1), &config.resume_settings())
+        .unwrap();
+    let mut config = TrainConfig::default();
+    config.set("resume", &checkpoint).unwrap();
+    assert!(config.validate().is_ok());
+    config.set("gamma", "0.9").unwrap();
+    assert!(config.validate().is_err());
+    fs::remove_file(&checkpoint).unwrap();
+
+    // 2^28 states only fit a sparse table
+    let mut config = TrainConfig::default();
+    config.set("encoder", "window:4x3").unwrap();
+    assert!(matches!(
+        config.validate(),
+        Err(GameError::InvalidConfig(_))
+    ));
+    config.set("storage", "sparse").unwrap();
+    assert!(config.validate().is_ok());
+}
+
+#[test]
+fn test_resume_needs_the_checkpoint_settings() {
+    let checkpoint = temp_path("settings_checkpoint.bin");
+    let mut saved = TrainConfig::default();
+    saved.set("learner", "double-q").unwrap();
+    saved.set("max-steps", "500").unwrap();
+    Agent::new()
+        .save_checkpoint(&checkpoint, &SimRng::new(1), &saved.resume_settings())
+        .unwrap();
+
+    let mut config = TrainConfig::default();
+    config.set("resume", &checkpoint).unwrap();
+    // A Double Q checkpoint resumed as plain Q-learning would keep its second table
+    config.set("max-steps", "500").unwrap();
+    assert!(matches!(
+        config.validate(),
+        Err(GameError::InvalidConfig(_))
+    ));
+    config.set("learner", "double-q").unwrap();
+    assert!(config.validate().is_ok());
+    config.set("max-steps", "1000").unwrap();
+    assert!(config.validate().is_err());
+    fs::remove_file(&checkpoint).unwrap();
+}
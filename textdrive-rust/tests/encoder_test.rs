@@ -0,0 +1,180 @@
+mod common;
+
+use common::temp_path;
+use std::io::Write;
+use textdrive::encoder::*;
+use textdrive::error::GameError;
+use textdrive::game::{Game, COLS_COUNT};
+use textdrive::hyperparams::Hyperparams;
+use textdrive::qlearning::{get_state, Agent, NUM_ACTIONS, STATE_SIZE};
+
+#[test]
+fn test_encoder_parse() {
+    assert_eq!("window".parse::<Encoder>().unwrap(), Encoder::default());
+    assert_eq!(
+        "gap:2x3".parse::<Encoder>().unwrap(),
+        Encoder::Gap(GapEncoder {
+            ahead: 2,
+            max_offset: 3
+        })
+    );
+    assert_eq!(
+        "walls".parse::<Encoder>().unwrap().id(),
+        WallOffsetEncoder::default().id()
+    );
+
+    assert!("grid".parse::<Encoder>().is_err());
+    assert!("gap:3".parse::<Encoder>().is_err());
+    assert!("window:0x2".parse::<Encoder>().is_err());
+    assert!("window:8x2".parse::<Encoder>().is_err());
+}
+
+#[test]
+fn test_encoder_id_round_trip() {
+    for id in ["window:3x2", "gap:3x4", "walls:2x5"] {
+        let encoder: Encoder = id.parse().unwrap();
+        assert_eq!(encoder.to_string(), id);
+    }
+}
+
+#[test]
+fn test_encoder_state_count() {
+    assert_eq!(Encoder::default().state_count(), STATE_SIZE);
+    assert_eq!(GapEncoder::default().state_count(), 729);
+    assert_eq!(WallOffsetEncoder::default().state_count(), 4096);
+}
+
+#[test]
+fn test_encoders_stay_in_range() {
+    let encoders: [Encoder; 3] = [
+        "window".parse().unwrap(),
+        "gap".parse().unwrap(),
+        "walls".parse().unwrap(),
+    ];
+    let mut game = Game::with_seed(7);
+
+    for _ in 0..100 {
+        game.scroll_course();
+        for encoder in &encoders {
+            assert!(encoder.encode(&game) < encoder.state_count());
+        }
+    }
+}
+
+#[test]
+fn test_window_encoder_matches_get_state() {
+    let mut game = Game::with_seed(3);
+    for _ in 0..50 {
+        game.scroll_course();
+        assert_eq!(Encoder::default().encode(&game), get_state(&game));
+    }
+}
+
+#[test]
+fn test_agent_table_sized_by_encoder() {
+    let encoder: Encoder = "gap".parse().unwrap();
+    let agent = Agent::with_encoder(encoder.clone(), Hyperparams::default());
+
+    assert_eq!(agent.q.len(), 729);
+    assert_eq!(
+        agent.state(&Game::with_seed(1)),
+        encoder.encode(&Game::with_seed(1))
+    );
+}
+
+#[test]
+fn test_agent_save_load_keeps_encoder() {
+    let path = temp_path("encoder.bin");
+    let encoder: Encoder = "walls:2x3".parse().unwrap();
+    let mut agent = Agent::with_encoder(encoder.clone(), Hyperparams::default());
+    agent.q[5] = [1.0, 2.0, 3.0];
+    agent.save(&path).unwrap();
+
+    let loaded = Agent::load(&path).unwrap();
+    let mismatch = Agent::load_with_encoder(&path, &Encoder::default());
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.encoder, encoder);
+    assert_eq!(loaded.q.len(), encoder.state_count());
+    assert_eq!(loaded.q[5], [1.0, 2.0, 3.0]);
+    assert!(matches!(mismatch, Err(GameError::InvalidAgentData(_))));
+}
+
+#[test]
+fn test_agent_load_legacy_file() {
+    let path = temp_path("legacy.bin");
+    let mut file = std::fs::File::create(&path).unwrap();
+    for _ in 0..STATE_SIZE * NUM_ACTIONS {
+        file.write_all(&0.5f64.to_le_bytes()).unwrap();
+    }
+    file.write_all(&0.25f64.to_le_bytes()).unwrap();
+    file.write_all(&42i32.to_le_bytes()).unwrap();
+    file.write_all(&9u64.to_le_bytes()).unwrap();
+    drop(file);
+
+    let agent = Agent::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(agent.encoder, Encoder::default());
+    assert_eq!(agent.q[STATE_SIZE - 1], [0.5; NUM_ACTIONS]);
+    assert_eq!(agent.epsilon, 0.25);
+    assert_eq!(agent.best_score, 42);
+    assert_eq!(agent.episodes, 9);
+}
+
+#[test]
+fn test_window_decode_round_trip() {
+    let window = WindowEncoder::default();
+    let mut game = Game::with_seed(11);
+    for _ in 0..30 {
+        game.scroll_course();
+    }
+    let state = window.encode(&game);
+    let walls = window.decode(state);
+
+    assert_eq!(walls.len(), window.ahead);
+    for (row, cells) in walls.iter().enumerate() {
+        let y = textdrive::game::PLAYER_ROW - 1 - row;
+        for (col, &wall) in cells.iter().enumerate() {
+            let x = game.player_x as i32 + col as i32 - window.range as i32;
+            let expected = (0..textdrive::game::COLS_COUNT as i32).contains(&x)
+                && game.get_cell(x as usize, y).is_wall();
+            assert_eq!(wall, expected);
+        }
+    }
+}
+
+/// Only knows which column the car is in
+struct ColumnEncoder;
+
+impl StateEncoder for ColumnEncoder {
+    fn encode(&self, game: &Game) -> usize {
+        game.player_x
+    }
+
+    fn state_count(&self) -> usize {
+        COLS_COUNT
+    }
+
+    fn id(&self) -> String {
+        "test-column".to_string()
+    }
+}
+
+#[test]
+fn test_custom_encoder_save_load() {
+    let path = temp_path("custom_encoder.bin");
+    let encoder = register(ColumnEncoder);
+    let mut agent = Agent::with_encoder(encoder.clone(), Hyperparams::default());
+    assert_eq!(agent.q.len(), COLS_COUNT);
+    assert_eq!(agent.state(&Game::with_seed(1)), COLS_COUNT / 2);
+    agent.q[2] = [1.0, 2.0, 3.0];
+    agent.save(&path).unwrap();
+
+    let loaded = Agent::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!("test-column".parse::<Encoder>().unwrap(), encoder);
+    assert_eq!(loaded.encoder, encoder);
+    assert_eq!(loaded.q[2], [1.0, 2.0, 3.0]);
+}
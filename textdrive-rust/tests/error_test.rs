@@ -29,3 +29,15 @@ fn test_game_error_source() {
 
     assert!(game_err.source().is_some());
 }
+
+#[test]
+fn test_game_error_shape_mismatch_display() {
+    let err = GameError::ShapeMismatch {
+        expected: 32768,
+        found: 100,
+    };
+    let display = format!("{}", err);
+
+    assert!(display.contains("32768"));
+    assert!(display.contains("100"));
+}
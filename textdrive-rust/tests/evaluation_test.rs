@@ -0,0 +1,53 @@
+use textdrive::evaluation::*;
+use textdrive::qlearning::Agent;
+
+#[test]
+fn test_summary_statistics() {
+    let evaluation = Evaluation::new(vec![40, 10, 30, 20, 50], 4);
+
+    assert_eq!(evaluation.scores(), &[10, 20, 30, 40, 50]);
+    assert_eq!(evaluation.mean(), 30.0);
+    assert_eq!(evaluation.median(), 30);
+    assert_eq!(evaluation.percentile(20.0), 10);
+    assert_eq!(evaluation.percentile(90.0), 50);
+    assert_eq!(evaluation.crash_rate(), 0.8);
+
+    let (low, high) = evaluation.mean_interval();
+    assert!(low < 30.0 && high > 30.0);
+    assert!((high - 30.0 - (30.0 - low)).abs() < 1e-9);
+}
+
+#[test]
+fn test_crash_rate_interval_stays_in_bounds() {
+    let (low, high) = Evaluation::new(vec![5; 20], 20).crash_rate_interval();
+    assert!(low > 0.8 && high == 1.0);
+
+    let (low, high) = Evaluation::new(vec![5; 20], 0).crash_rate_interval();
+    assert!(low == 0.0 && high < 0.2);
+}
+
+#[test]
+fn test_survival_curve() {
+    let evaluation = Evaluation::new(vec![10, 20, 30, 40], 3);
+
+    assert_eq!(evaluation.survival(0), 1.0);
+    assert_eq!(evaluation.survival(25), 0.5);
+    assert_eq!(evaluation.survival(41), 0.0);
+    assert_eq!(
+        evaluation.survival_curve(4),
+        vec![(10, 1.0), (20, 0.75), (30, 0.5), (40, 0.25)]
+    );
+}
+
+#[test]
+fn test_suite_is_reproducible() {
+    let suite = EvalSuite::new(7, 5, 300);
+    assert_eq!(suite.seeds(), EvalSuite::new(7, 5, 300).seeds());
+    assert_ne!(suite.seeds(), EvalSuite::new(8, 5, 300).seeds());
+
+    let agent = Agent::new();
+    let first = suite.evaluate(&agent);
+    assert_eq!(first, suite.evaluate(&agent));
+    assert_eq!(first.courses(), 5);
+    assert!(first.scores().iter().all(|&s| s <= 300));
+}
@@ -0,0 +1,134 @@
+mod common;
+
+use common::{filled_agent, temp_path};
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use std::io::{Cursor, Write};
+use textdrive::error::GameError;
+use textdrive::export::*;
+use textdrive::policy::{load_policy, Policy};
+use textdrive::qlearning::{best_action, Action, Agent};
+
+fn round_trip(policy: &QuantizedPolicy, compress: bool) -> (QuantizedPolicy, usize) {
+    let mut bytes = Vec::new();
+    policy.write_to(&mut bytes, compress).unwrap();
+    let size = bytes.len();
+    (
+        QuantizedPolicy::read_from(Cursor::new(bytes)).unwrap(),
+        size,
+    )
+}
+
+#[test]
+fn test_precision_parse() {
+    assert_eq!("f16".parse::<Precision>().unwrap(), Precision::F16);
+    assert_eq!("argmax".parse::<Precision>().unwrap(), Precision::Argmax);
+    assert!("f8".parse::<Precision>().is_err());
+}
+
+#[test]
+fn test_quantized_values_are_close() {
+    let agent = filled_agent();
+
+    for precision in [Precision::F16, Precision::I8] {
+        let policy = QuantizedPolicy::quantize(&agent, precision);
+        let report = policy.compare(&agent);
+
+        assert_eq!(report.states, agent.q.len());
+        // i8 steps are 1/127 of the largest value in a state
+        assert!(report.max_error < 80.0 / 127.0, "{}", precision);
+        assert!(report.changed_fraction() < 0.05, "{}", precision);
+    }
+}
+
+#[test]
+fn test_argmax_keeps_every_greedy_action() {
+    let agent = filled_agent();
+    let policy = QuantizedPolicy::quantize(&agent, Precision::Argmax);
+
+    assert_eq!(policy.compare(&agent).changed_actions, 0);
+    for state in [0, 1, 2, 3, 4, 1000, 32767] {
+        let best = best_action(&agent.q_values(state));
+        assert_eq!(policy.best_action(state), best);
+        assert_eq!(policy.q_values(state)[best as usize], 1.0);
+    }
+}
+
+#[test]
+fn test_policy_round_trip_and_sizes() {
+    let agent = filled_agent();
+    let argmax = QuantizedPolicy::quantize(&agent, Precision::Argmax);
+    let i8 = QuantizedPolicy::quantize(&agent, Precision::I8);
+
+    let (loaded, argmax_size) = round_trip(&argmax, false);
+    assert_eq!(loaded, argmax);
+    assert!(argmax_size < agent.q.len() / 4 + 64);
+
+    let (loaded, _) = round_trip(&i8, true);
+    assert_eq!(loaded, i8);
+}
+
+#[test]
+fn test_policy_compression_shrinks_uniform_table() {
+    let agent = Agent::new();
+    let policy = QuantizedPolicy::quantize(&agent, Precision::F16);
+
+    let (_, plain) = round_trip(&policy, false);
+    let (loaded, compressed) = round_trip(&policy, true);
+
+    assert_eq!(loaded, policy);
+    assert!(compressed * 10 < plain);
+}
+
+#[test]
+fn test_policy_checksum_failure() {
+    let policy = QuantizedPolicy::quantize(&filled_agent(), Precision::Argmax);
+    let mut bytes = Vec::new();
+    policy.write_to(&mut bytes, false).unwrap();
+    bytes[100] ^= 1;
+
+    assert!(matches!(
+        QuantizedPolicy::read_from(Cursor::new(bytes)),
+        Err(GameError::ChecksumMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_load_policy_reads_export() {
+    let path = &temp_path("export.tdp");
+    let mut agent = Agent::new();
+    agent.q[9] = [0.0, 0.0, 1.0];
+    QuantizedPolicy::quantize(&agent, Precision::Argmax)
+        .save(path, true)
+        .unwrap();
+
+    let policy = load_policy(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    assert_eq!(policy.best_action(9), Action::Right);
+    assert!(!std::path::Path::new(&format!("{}.tmp", path)).exists());
+}
+
+#[test]
+fn test_policy_rejects_oversized_compressed_payload() {
+    let policy = QuantizedPolicy::quantize(&filled_agent(), Precision::Argmax);
+    let mut plain = Vec::new();
+    policy.write_to(&mut plain, false).unwrap();
+    let mut compressed = Vec::new();
+    policy.write_to(&mut compressed, true).unwrap();
+
+    // Keep the compressed header, but deflate the table with junk after it
+    let header = 8 + 4 + policy.encoder().to_string().len() + 8;
+    let mut table = plain[header..plain.len() - 4].to_vec();
+    table.extend_from_slice(&[0; 4096]);
+    let mut deflate = DeflateEncoder::new(compressed[..header].to_vec(), Compression::best());
+    deflate.write_all(&table).unwrap();
+    let mut bytes = deflate.finish().unwrap();
+    let checksum = crc32fast::hash(&bytes);
+    bytes.extend_from_slice(&checksum.to_le_bytes());
+
+    assert!(matches!(
+        QuantizedPolicy::read_from(Cursor::new(bytes)),
+        Err(GameError::ShapeMismatch { .. })
+    ));
+}
@@ -89,3 +89,70 @@ fn test_has_collision_before_filled() {
     let game = Game::new();
     assert!(!game.has_collision());
 }
+
+#[test]
+fn test_game_with_seed_is_deterministic() {
+    let mut a = Game::with_seed(42);
+    let mut b = Game::with_seed(42);
+
+    for _ in 0..50 {
+        a.scroll_course();
+        b.scroll_course();
+    }
+
+    for y in 0..ROWS_COUNT {
+        for x in 0..COLS_COUNT {
+            assert_eq!(a.get_cell(x, y), b.get_cell(x, y));
+        }
+    }
+    assert_eq!(a.seed(), 42);
+}
+
+#[test]
+fn test_game_restart_replays_course() {
+    let mut game = Game::with_seed(7);
+    for _ in 0..20 {
+        game.scroll_course();
+    }
+    let first: Vec<Cell> = (0..COLS_COUNT).map(|x| game.get_cell(x, 0)).collect();
+
+    game.restart();
+    assert_eq!(game.distance, 0);
+    for _ in 0..20 {
+        game.scroll_course();
+    }
+    let second: Vec<Cell> = (0..COLS_COUNT).map(|x| game.get_cell(x, 0)).collect();
+
+    assert_eq!(first, second);
+}
+
+#[test]
+fn test_scroll_moves_rows_down() {
+    let mut game = Game::with_seed(3);
+    for _ in 0..40 {
+        let before: Vec<u16> = (0..ROWS_COUNT).map(|y| game.row_bits(y)).collect();
+        game.scroll_course();
+
+        for y in 1..ROWS_COUNT {
+            assert_eq!(game.row_bits(y), before[y - 1]);
+        }
+        for y in 0..ROWS_COUNT {
+            for x in 0..COLS_COUNT {
+                let wall = game.row_bits(y) >> x & 1 == 1;
+                assert_eq!(game.get_cell(x, y).is_wall(), wall);
+            }
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "off the board")]
+fn test_row_past_bottom_panics() {
+    Game::new().row_bits(ROWS_COUNT);
+}
+
+#[test]
+#[should_panic(expected = "off the board")]
+fn test_cell_past_right_edge_panics() {
+    Game::new().get_cell(COLS_COUNT, 0);
+}
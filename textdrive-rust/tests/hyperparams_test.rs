@@ -0,0 +1,103 @@
+use textdrive::game::Game;
+use textdrive::hyperparams::*;
+
+fn schedule(decay: Decay) -> Schedule {
+    Schedule {
+        start: 1.0,
+        min: 0.0,
+        decay,
+        unit: DecayUnit::Episode,
+    }
+}
+
+#[test]
+fn test_schedule_constant() {
+    let s = Schedule::constant(0.3);
+    assert_eq!(s.value(0), 0.3);
+    assert_eq!(s.value(1_000_000), 0.3);
+}
+
+#[test]
+fn test_schedule_exponential() {
+    let s = Schedule::exponential(1.0, 0.5);
+    assert_eq!(s.value(0), 1.0);
+    assert_eq!(s.value(2), 0.25);
+}
+
+#[test]
+fn test_schedule_linear() {
+    let s = schedule(Decay::Linear { end: 0.1, over: 10 });
+    assert_eq!(s.value(0), 1.0);
+    assert!((s.value(5) - 0.55).abs() < 1e-12);
+    assert!((s.value(10) - 0.1).abs() < 1e-12);
+    assert!((s.value(100) - 0.1).abs() < 1e-12);
+}
+
+#[test]
+fn test_schedule_step() {
+    let s = schedule(Decay::Step {
+        factor: 0.5,
+        every: 10,
+    });
+    assert_eq!(s.value(9), 1.0);
+    assert_eq!(s.value(10), 0.5);
+    assert_eq!(s.value(25), 0.25);
+}
+
+#[test]
+fn test_schedule_cosine() {
+    let s = schedule(Decay::Cosine {
+        end: 0.0,
+        over: 100,
+    });
+    assert_eq!(s.value(0), 1.0);
+    assert!((s.value(50) - 0.5).abs() < 1e-12);
+    assert!(s.value(100).abs() < 1e-12);
+}
+
+#[test]
+fn test_schedule_min_floor() {
+    let s = Schedule::exponential(1.0, 0.5).with_min(0.05);
+    assert_eq!(s.value(100), 0.05);
+}
+
+#[test]
+fn test_schedule_unit() {
+    let s = Schedule::exponential(1.0, 0.5).per(DecayUnit::Step);
+    let clock = ScheduleClock {
+        episodes: 0,
+        steps: 1,
+    };
+    assert_eq!(s.value_at(clock), 0.5);
+}
+
+#[test]
+fn test_hyperparams_reward() {
+    let params = Hyperparams {
+        survival_reward: 2.0,
+        ..Hyperparams::default()
+    };
+    assert_eq!(params.reward(&Game::new()), 2.0);
+}
+
+#[test]
+fn test_schedule_text_round_trip() {
+    let schedules = [
+        Schedule::constant(0.2),
+        Schedule::exponential(1.0, 0.9995).with_min(0.01),
+        Schedule {
+            start: 0.5,
+            min: 0.0,
+            decay: Decay::Cosine {
+                end: 0.05,
+                over: 20000,
+            },
+            unit: DecayUnit::Step,
+        },
+    ];
+
+    for schedule in schedules {
+        assert_eq!(schedule.to_string().parse::<Schedule>().unwrap(), schedule);
+    }
+    assert!("exponential start=1".parse::<Schedule>().is_err());
+}
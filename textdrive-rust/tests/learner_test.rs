@@ -0,0 +1,78 @@
+use textdrive::error::GameError;
+use textdrive::learner::*;
+use textdrive::qlearning::{Action, Agent};
+
+fn transition(next_action: Action) -> Transition {
+    Transition {
+        state: 0,
+        action: Action::Stay,
+        reward: 1.0,
+        next_state: 1,
+        next_action,
+    }
+}
+
+#[test]
+fn test_learner_from_str() {
+    for learner in Learner::all() {
+        assert_eq!(learner.name().parse::<Learner>().unwrap(), learner);
+    }
+
+    match "td-gammon".parse::<Learner>() {
+        Err(GameError::InvalidConfig(_)) => {}
+        _ => panic!("Expected GameError::InvalidConfig"),
+    }
+}
+
+#[test]
+fn test_sarsa_uses_next_action() {
+    let mut agent = Agent::new();
+    agent.q[1] = [10.0, 0.0, 0.0];
+
+    Learner::Sarsa.update(&mut agent, &transition(Action::Stay));
+    let alpha = agent.alpha;
+    assert_eq!(agent.q[0][Action::Stay as usize], alpha * 1.0);
+
+    agent.q[0] = [0.0; 3];
+    Learner::Sarsa.update(&mut agent, &transition(Action::Left));
+    let gamma = agent.params.gamma;
+    assert_eq!(
+        agent.q[0][Action::Stay as usize],
+        alpha * (1.0 + gamma * 10.0)
+    );
+}
+
+#[test]
+fn test_expected_q() {
+    let q = [3.0, 0.0, 0.0];
+
+    assert_eq!(expected_q(&q, 0.0), 3.0);
+    assert!((expected_q(&q, 1.0) - 1.0).abs() < 1e-12);
+    assert!((expected_q(&q, 0.3) - (0.8 * 3.0)).abs() < 1e-12);
+}
+
+#[test]
+fn test_expected_sarsa_update() {
+    let mut agent = Agent::new();
+    agent.epsilon = 0.0;
+    agent.q[1] = [0.0, 0.0, 5.0];
+
+    Learner::ExpectedSarsa.update(&mut agent, &transition(Action::Left));
+
+    let expected = agent.alpha * (1.0 + agent.params.gamma * 5.0);
+    assert!((agent.q[0][Action::Stay as usize] - expected).abs() < 1e-12);
+}
+
+#[test]
+fn test_double_q_updates_one_table() {
+    let mut agent = Agent::new();
+    Learner::DoubleQ.prepare(&mut agent);
+    assert!(agent.q2.is_some());
+
+    Learner::DoubleQ.update(&mut agent, &transition(Action::Stay));
+
+    let q1 = agent.q[0][Action::Stay as usize];
+    let q2 = agent.q2.as_ref().unwrap()[0][Action::Stay as usize];
+    assert!((q1 == 0.0) != (q2 == 0.0));
+    assert_eq!(agent.q_values(0)[Action::Stay as usize], (q1 + q2) / 2.0);
+}
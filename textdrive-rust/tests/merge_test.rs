@@ -0,0 +1,150 @@
+mod common;
+
+use common::empty_agent;
+use textdrive::error::GameError;
+use textdrive::hyperparams::Hyperparams;
+use textdrive::merge::*;
+use textdrive::model::read_model;
+use textdrive::qlearning::{Action, Agent};
+use textdrive::storage::StorageKind;
+
+#[test]
+fn test_diff_lists_changed_greedy_actions() {
+    let mut left = empty_agent(StorageKind::Sparse);
+    let mut right = empty_agent(StorageKind::Sparse);
+    left.q[1] = [1.0, 0.0, 0.0];
+    right.q[1] = [0.0, 0.0, 1.0];
+    left.q[2] = [0.0, 5.0, 0.0];
+    right.q[2] = [0.0, 4.0, 0.0];
+
+    let diffs = diff(&left, &right).unwrap();
+
+    assert_eq!(diffs.len(), 1);
+    assert_eq!(diffs[0].state, 1);
+    assert_eq!(diffs[0].left, Action::Left);
+    assert_eq!(diffs[0].right, Action::Right);
+    assert_eq!(diffs[0].visits, None);
+}
+
+#[test]
+fn test_diff_sorts_by_visits() {
+    let mut left = empty_agent(StorageKind::Sparse);
+    let mut right = empty_agent(StorageKind::Sparse);
+    left.track_visits();
+    right.track_visits();
+    for state in [3, 4] {
+        left.q[state] = [1.0, 0.0, 0.0];
+        right.q[state] = [0.0, 1.0, 0.0];
+    }
+    left.record_visit(4);
+    right.record_visit(4);
+    right.record_visit(3);
+
+    let diffs = diff(&left, &right).unwrap();
+
+    assert_eq!(diffs[0].state, 4);
+    assert_eq!(diffs[0].visits, Some(2));
+    assert_eq!(diffs[1].visits, Some(1));
+}
+
+#[test]
+fn test_merge_weighted_average() {
+    let mut a = empty_agent(StorageKind::Sparse);
+    let mut b = empty_agent(StorageKind::Sparse);
+    a.q[7] = [4.0, 0.0, 0.0];
+    b.q[7] = [0.0, 8.0, 0.0];
+    a.best_score = 10;
+    b.best_score = 30;
+
+    let merged = merge(&[(&a, 3.0), (&b, 1.0)], MergeMode::Average).unwrap();
+
+    assert_eq!(merged.q[7], [3.0, 2.0, 0.0]);
+    assert_eq!(merged.best_score, 30);
+    assert_eq!(merged.q.kind(), StorageKind::Sparse);
+}
+
+#[test]
+fn test_merge_average_uses_visits() {
+    let mut a = empty_agent(StorageKind::Sparse);
+    let mut b = empty_agent(StorageKind::Sparse);
+    a.track_visits();
+    b.track_visits();
+    a.q[7] = [4.0, 0.0, 0.0];
+    b.q[7] = [0.0, 4.0, 0.0];
+    for _ in 0..3 {
+        b.record_visit(7);
+    }
+    a.record_visit(7);
+
+    let merged = merge(&[(&a, 1.0), (&b, 1.0)], MergeMode::Average).unwrap();
+
+    assert_eq!(merged.q[7], [1.0, 3.0, 0.0]);
+    assert_eq!(merged.visit_count(7), Some(4));
+}
+
+#[test]
+fn test_merge_max_confidence() {
+    let mut a = empty_agent(StorageKind::Sparse);
+    let mut b = empty_agent(StorageKind::Sparse);
+    a.q[5] = [1.0, 0.9, 0.0];
+    b.q[5] = [0.0, 0.0, 2.0];
+
+    let merged = merge(&[(&a, 1.0), (&b, 1.0)], MergeMode::MaxConfidence).unwrap();
+
+    assert_eq!(merged.q[5], [0.0, 0.0, 2.0]);
+}
+
+#[test]
+fn test_merge_rejects_different_encoders() {
+    let a = empty_agent(StorageKind::Sparse);
+    let b = Agent::with_storage(
+        "gap".parse().unwrap(),
+        Hyperparams::default(),
+        StorageKind::Sparse,
+    );
+
+    assert!(merge(&[(&a, 1.0), (&b, 1.0)], MergeMode::Average).is_err());
+    assert!(diff(&a, &b).is_err());
+}
+
+#[test]
+fn test_merge_rejects_all_zero_weights() {
+    let mut a = empty_agent(StorageKind::Sparse);
+    let b = empty_agent(StorageKind::Sparse);
+    a.q[3] = [1.0, 2.0, 3.0];
+
+    assert!(matches!(
+        merge(&[(&a, 0.0), (&b, 0.0)], MergeMode::Average),
+        Err(GameError::InvalidConfig(_))
+    ));
+    assert!(merge(&[(&a, -1.0), (&b, 2.0)], MergeMode::Average).is_err());
+    assert!(merge(&[(&a, f64::NAN), (&b, 1.0)], MergeMode::Average).is_err());
+    assert!(merge(&[(&a, 0.0), (&b, 1.0)], MergeMode::Average).is_ok());
+    assert!(valid_weights([0.0, 0.5]));
+    assert!(!valid_weights([0.0, 0.0]));
+}
+
+#[test]
+fn test_visits_survive_save_load() {
+    let mut agent = empty_agent(StorageKind::Sparse);
+    agent.track_visits();
+    agent.record_visit(11);
+    agent.record_visit(11);
+    let mut bytes = Vec::new();
+    agent.save_to(&mut bytes).unwrap();
+
+    let (loaded, info) = read_model(&bytes).unwrap();
+
+    assert_eq!(loaded.visit_count(11), Some(2));
+    assert_eq!(loaded.visit_count(12), Some(0));
+    assert_eq!(info.visited, Some(1));
+}
+
+#[test]
+fn test_merge_mode_parse() {
+    assert_eq!(
+        "max-confidence".parse::<MergeMode>().unwrap(),
+        MergeMode::MaxConfidence
+    );
+    assert!("median".parse::<MergeMode>().is_err());
+}
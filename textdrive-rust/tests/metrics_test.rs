@@ -0,0 +1,98 @@
+mod common;
+
+use common::temp_path;
+use textdrive::metrics::*;
+use textdrive::tensorboard::{crc32c, EventWriter};
+
+fn sample_metrics() -> EpisodeMetrics {
+    EpisodeMetrics {
+        episode: 3,
+        steps: 12,
+        total_reward: -88.0,
+        distance: 12,
+        epsilon: 0.5,
+        alpha: 0.2,
+        mean_abs_td_error: 1.25,
+        states_visited: 40,
+        evaluation: None,
+    }
+}
+
+#[test]
+fn test_csv_rows() {
+    let mut log = MetricsLog::new(Vec::new(), MetricsFormat::Csv);
+    log.record(&sample_metrics()).unwrap();
+    let mut evaluated = sample_metrics();
+    evaluated.evaluation = Some(EvalMetrics {
+        mean: 150.5,
+        median: 120,
+        crash_rate: 0.75,
+    });
+    log.record(&evaluated).unwrap();
+
+    let text = String::from_utf8(log.into_inner()).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[0], EpisodeMetrics::COLUMNS.join(","));
+    assert_eq!(lines[1], "3,12,-88,12,0.5,0.2,1.25,40,,,");
+    assert_eq!(lines[2], "3,12,-88,12,0.5,0.2,1.25,40,150.5,120,0.75");
+}
+
+#[test]
+fn test_jsonl_skips_missing_evaluation() {
+    let mut log = MetricsLog::new(Vec::new(), MetricsFormat::Jsonl);
+    log.record(&sample_metrics()).unwrap();
+
+    let text = String::from_utf8(log.into_inner()).unwrap();
+    assert_eq!(
+        text,
+        "{\"episode\":3,\"steps\":12,\"return\":-88,\"distance\":12,\"epsilon\":0.5,\
+         \"alpha\":0.2,\"td_error\":1.25,\"states_visited\":40}\n"
+    );
+}
+
+#[test]
+fn test_append_keeps_a_single_header() {
+    let path = temp_path("metrics.csv");
+    let mut log = MetricsLog::create(&path).unwrap();
+    log.record(&sample_metrics()).unwrap();
+    log.flush().unwrap();
+    let mut log = MetricsLog::append(&path).unwrap();
+    log.record(&sample_metrics()).unwrap();
+    log.flush().unwrap();
+
+    let text = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(text.lines().count(), 3);
+    assert_eq!(text.matches("episode,").count(), 1);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_format_from_extension() {
+    assert_eq!(
+        MetricsFormat::from_path("run/metrics.jsonl").unwrap(),
+        MetricsFormat::Jsonl
+    );
+    assert_eq!(
+        MetricsFormat::from_path("metrics.csv").unwrap(),
+        MetricsFormat::Csv
+    );
+    assert!(MetricsFormat::from_path("metrics.txt").is_err());
+}
+
+#[test]
+fn test_tensorboard_records() {
+    assert_eq!(crc32c(b"123456789"), 0xE306_9283);
+
+    let dir = temp_path("tensorboard");
+    let mut events = EventWriter::create(&dir).unwrap();
+    events.add_metrics(&sample_metrics()).unwrap();
+    events.flush().unwrap();
+
+    let file = std::fs::read_dir(&dir).unwrap().next().unwrap().unwrap();
+    let bytes = std::fs::read(file.path()).unwrap();
+    let first_len = u64::from_le_bytes(bytes[..8].try_into().unwrap()) as usize;
+    let first = &bytes[12..12 + first_len];
+    assert!(first.windows(13).any(|w| w == b"brain.Event:2"));
+    assert!(bytes.windows(8).any(|w| w == b"td_error"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
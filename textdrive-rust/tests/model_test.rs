@@ -0,0 +1,222 @@
+mod common;
+
+use common::{sample_agent, temp_path};
+use textdrive::challenge::Date;
+use textdrive::config::TrainConfig;
+use textdrive::encoder::Encoder;
+use textdrive::error::GameError;
+use textdrive::model::*;
+use textdrive::qlearning::{Agent, NUM_ACTIONS, STATE_SIZE};
+use textdrive::rng::SimRng;
+use textdrive::stopping::StopReason;
+use textdrive::storage::StorageKind;
+use textdrive::traces::TraceKind;
+
+fn encode(agent: &Agent) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    write_model(agent, Date::new(2026, 10, 19), &mut bytes).unwrap();
+    bytes
+}
+
+fn legacy_bytes(rows: usize) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for _ in 0..rows * NUM_ACTIONS {
+        bytes.extend_from_slice(&0.5f64.to_le_bytes());
+    }
+    bytes.extend_from_slice(&0.25f64.to_le_bytes());
+    bytes.extend_from_slice(&7i32.to_le_bytes());
+    bytes.extend_from_slice(&3u64.to_le_bytes());
+    bytes
+}
+
+#[test]
+fn test_model_round_trip() {
+    let agent = sample_agent();
+    let (loaded, info) = read_model(&encode(&agent)).unwrap();
+
+    assert!(encode(&agent).starts_with(MODEL_MAGIC));
+    assert_eq!(loaded.q[42], [1.5, -2.0, 0.25]);
+    assert_eq!(loaded.params, agent.params);
+    assert_eq!(loaded.encoder, agent.encoder);
+    assert_eq!(loaded.clock, agent.clock);
+    assert_eq!(loaded.epsilon, 0.3);
+
+    assert_eq!(info.version, MODEL_VERSION);
+    assert_eq!(info.storage, StorageKind::Sparse);
+    assert_eq!(info.states, 729);
+    assert_eq!(info.stored, 1);
+    assert_eq!(info.best_score, 321);
+    assert_eq!(info.trained, Date::new(2026, 10, 19));
+}
+
+#[test]
+fn test_model_checksum_failure() {
+    let mut bytes = encode(&sample_agent());
+    let middle = bytes.len() / 2;
+    bytes[middle] ^= 0xff;
+
+    assert!(matches!(
+        read_model(&bytes),
+        Err(GameError::ChecksumMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_model_truncated() {
+    let bytes = encode(&sample_agent());
+    let result = read_model(&bytes[..bytes.len() - 10]);
+
+    match result {
+        Err(GameError::InvalidAgentData(msg)) => assert!(msg.contains("truncated")),
+        _ => panic!("Expected a truncation error"),
+    }
+}
+
+#[test]
+fn test_model_chunk_length_overflow() {
+    let mut bytes = encode(&sample_agent());
+    // Length of the first chunk, right after the header and its tag
+    bytes[12..20].copy_from_slice(&u64::MAX.to_le_bytes());
+
+    assert!(matches!(
+        read_model(&bytes),
+        Err(GameError::InvalidAgentData(_))
+    ));
+}
+
+#[test]
+fn test_model_unsupported_version() {
+    let mut bytes = encode(&sample_agent());
+    bytes[4] = 9;
+
+    assert!(matches!(
+        read_model(&bytes),
+        Err(GameError::UnsupportedVersion(9))
+    ));
+}
+
+#[test]
+fn test_model_non_finite_value() {
+    let mut agent = sample_agent();
+    agent.q[7][1] = f64::NAN;
+
+    assert!(matches!(
+        read_model(&encode(&agent)),
+        Err(GameError::NonFiniteValue(ref what)) if what == "Q-value in state 7"
+    ));
+
+    let mut agent = sample_agent();
+    agent.epsilon = f64::INFINITY;
+    assert!(matches!(
+        read_model(&encode(&agent)),
+        Err(GameError::NonFiniteValue(ref what)) if what == "epsilon"
+    ));
+}
+
+#[test]
+fn test_legacy_model_loads() {
+    let (agent, info) = read_model(&legacy_bytes(STATE_SIZE)).unwrap();
+
+    assert_eq!(info.version, 0);
+    assert_eq!(info.trained, None);
+    assert_eq!(agent.encoder, Encoder::default());
+    assert_eq!(agent.q[0], [0.5; NUM_ACTIONS]);
+    assert_eq!(agent.best_score, 7);
+    assert_eq!(agent.episodes, 3);
+}
+
+#[test]
+fn test_legacy_model_shape_mismatch() {
+    assert!(matches!(
+        read_model(&legacy_bytes(100)),
+        Err(GameError::ShapeMismatch {
+            expected: STATE_SIZE,
+            found: 100
+        })
+    ));
+}
+
+#[test]
+fn test_migrate_legacy_file() {
+    let path = &temp_path("migrate.bin");
+    std::fs::write(path, legacy_bytes(STATE_SIZE)).unwrap();
+
+    let old_version = migrate(path).unwrap();
+    let info = ModelInfo::load(path).unwrap();
+    let again = migrate(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    assert_eq!(old_version, 0);
+    assert_eq!(info.version, MODEL_VERSION);
+    assert_eq!(info.best_score, 7);
+    assert_eq!(again, MODEL_VERSION);
+}
+
+#[test]
+fn test_checkpoint_keeps_both_tables_and_generators() {
+    let mut agent = sample_agent();
+    let mut q2 = agent.q.clone();
+    q2[42] = [0.5, 0.0, 0.0];
+    agent.q2 = Some(q2);
+    agent.seed(9);
+    let courses = SimRng::new(10);
+
+    let mut config = TrainConfig::default();
+    config.set("learner", "double-q").unwrap();
+    config.set("max-steps", "500").unwrap();
+
+    let mut bytes = Vec::new();
+    write_checkpoint(&agent, &courses, &config.resume_settings(), &mut bytes).unwrap();
+    let (loaded, loaded_courses) = read_checkpoint(&bytes).unwrap();
+
+    assert_eq!(loaded.q, agent.q);
+    assert_eq!(loaded.q2, agent.q2);
+    assert_eq!(loaded.rng, agent.rng);
+    assert_eq!(loaded_courses, courses);
+    // Still a model: plays on the averaged tables
+    let (model, info) = read_model(&bytes).unwrap();
+    assert_eq!(model.q_values(42), [1.0, -1.0, 0.125]);
+    assert_eq!(info.settings, Some(config.resume_settings()));
+}
+
+#[test]
+fn test_checkpoint_records_multi_step_settings() {
+    let mut config = TrainConfig::default();
+    config.set("lambda", "0.8").unwrap();
+    config.set("trace", "peng").unwrap();
+    let mut bytes = Vec::new();
+    write_checkpoint(
+        &sample_agent(),
+        &SimRng::new(1),
+        &config.resume_settings(),
+        &mut bytes,
+    )
+    .unwrap();
+
+    let (_, info) = read_model(&bytes).unwrap();
+    let settings = info.settings.unwrap();
+    assert_eq!(settings.lambda, Some(0.8));
+    assert_eq!(settings.trace, Some(TraceKind::Peng));
+    assert_eq!(settings.n_step, None);
+    // Plain models don't record how they were trained
+    let (_, info) = read_model(&encode(&sample_agent())).unwrap();
+    assert_eq!(info.settings, None);
+}
+
+#[test]
+fn test_plain_model_is_not_a_checkpoint() {
+    let result = read_checkpoint(&encode(&sample_agent()));
+    assert!(matches!(result, Err(GameError::InvalidAgentData(_))));
+}
+
+#[test]
+fn test_stop_reason_in_metadata() {
+    let mut agent = sample_agent();
+    let (_, info) = read_model(&encode(&agent)).unwrap();
+    assert_eq!(info.stop_reason, None);
+
+    agent.stop_reason = Some(StopReason::Converged);
+    let (loaded, info) = read_model(&encode(&agent)).unwrap();
+    assert_eq!(info.stop_reason, Some(StopReason::Converged));
+    assert_eq!(loaded.stop_reason, Some(StopReason::Converged));
+}
@@ -0,0 +1,69 @@
+use textdrive::learner::Learner;
+use textdrive::parallel::{absorb, run_round};
+use textdrive::qlearning::Agent;
+use textdrive::rng::SimRng;
+use textdrive::traces::CreditAssignment;
+use textdrive::training::train_episode;
+
+/// Runs Q-learning episodes and returns the distance of each
+fn train(agent: &mut Agent, courses: &mut SimRng, episodes: usize) -> Vec<i32> {
+    let mut credit = CreditAssignment::OneStep;
+    (0..episodes)
+        .map(|_| train_episode(agent, Learner::QLearning, &mut credit, courses, 200).distance)
+        .collect()
+}
+
+fn train_parallel(seed: u64, workers: usize, rounds: usize) -> Agent {
+    let mut agent = Agent::new();
+    agent.seed(seed);
+    agent.track_visits();
+    let mut courses = SimRng::new(seed + 1);
+    for _ in 0..rounds {
+        run_round(&mut agent, &mut courses, workers, 12, train).unwrap();
+    }
+    agent
+}
+
+#[test]
+fn test_same_seed_and_workers_train_identical_tables() {
+    let first = train_parallel(5, 4, 3);
+    let second = train_parallel(5, 4, 3);
+    let other = train_parallel(6, 4, 3);
+
+    assert_eq!(first.q, second.q);
+    assert_eq!(first.visits, second.visits);
+    assert_ne!(first.q, other.q);
+}
+
+#[test]
+fn test_round_counts_every_episode() {
+    let mut agent = Agent::new();
+    agent.track_visits();
+    let mut courses = SimRng::new(1);
+    let distances = run_round(&mut agent, &mut courses, 3, 7, train).unwrap();
+
+    assert_eq!(distances.len(), 7);
+    assert_eq!(agent.episodes, 7);
+    assert_eq!(agent.clock.episodes, 7);
+    assert_eq!(agent.best_score, *distances.iter().max().unwrap());
+    let steps: u64 = agent.visits.as_ref().unwrap().values().sum();
+    assert_eq!(agent.clock.steps, steps);
+}
+
+#[test]
+fn test_absorb_weights_rows_by_visits() {
+    let mut agent = Agent::new();
+    agent.track_visits();
+    agent.record_visit(3);
+    // Workers count only the visits of their own round
+    let mut explorer = agent.clone();
+    explorer.visits = Some(Default::default());
+    let idle = explorer.clone();
+    explorer.q[3] = [1.0, 2.0, 3.0];
+    explorer.record_visit(3);
+
+    absorb(&mut agent, &[explorer, idle]).unwrap();
+
+    assert_eq!(agent.q[3], [1.0, 2.0, 3.0]);
+    assert_eq!(agent.visit_count(3), Some(2));
+}
@@ -0,0 +1,145 @@
+mod common;
+
+use common::{temp_path, trained_agent};
+use std::io::Cursor;
+use textdrive::error::GameError;
+use textdrive::game::Game;
+use textdrive::learner::Learner;
+use textdrive::policy::*;
+use textdrive::qlearning::{Action, Agent, NUM_ACTIONS};
+use textdrive::storage::StorageKind;
+
+#[test]
+fn test_save_to_load_from_memory() {
+    let agent = trained_agent(StorageKind::Dense);
+    let mut bytes = Vec::new();
+    agent.save_to(&mut bytes).unwrap();
+
+    let loaded = Agent::load_from(Cursor::new(bytes)).unwrap();
+
+    assert_eq!(loaded.q, agent.q);
+}
+
+#[test]
+fn test_mapped_policy_matches_agent() {
+    for storage in [StorageKind::Dense, StorageKind::Sparse] {
+        let path = temp_path(&format!("mapped_{}.bin", storage));
+        let agent = trained_agent(storage);
+        agent.save(&path).unwrap();
+
+        let mapped = MappedPolicy::open(&path).unwrap();
+
+        assert_eq!(mapped.info().storage, storage);
+        for state in [0, 1, 500, 501, 32767] {
+            assert_eq!(Policy::q_values(&mapped, state), agent.q[state]);
+        }
+        assert_eq!(mapped.best_action(500), Action::Stay);
+        let game = Game::with_seed(5);
+        assert_eq!(mapped.state(&game), agent.state(&game));
+
+        drop(mapped);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
+
+#[test]
+fn test_saving_over_mapped_model() {
+    let path = temp_path("mapped_resave.bin");
+    let agent = trained_agent(StorageKind::Dense);
+    agent.save(&path).unwrap();
+    let mapped = MappedPolicy::open(&path).unwrap();
+
+    let mut retrained = agent.clone();
+    retrained.q[500] = [9.0, 9.0, 9.0];
+    retrained.save(&path).unwrap();
+
+    // The map keeps the file it opened; the path now holds the new one
+    assert_eq!(Policy::q_values(&mapped, 500), agent.q[500]);
+    assert_eq!(Agent::load(&path).unwrap().q[500], [9.0, 9.0, 9.0]);
+    assert!(!std::path::Path::new(&format!("{}.tmp", path)).exists());
+
+    drop(mapped);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_mapped_policy_rejects_old_format() {
+    let path = temp_path("mapped_legacy.bin");
+    std::fs::write(&path, vec![0u8; 64]).unwrap();
+
+    let result = MappedPolicy::open(&path);
+    let fallback = load_policy(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(matches!(result, Err(GameError::UnsupportedVersion(0))));
+    assert!(matches!(fallback, Err(GameError::ShapeMismatch { .. })));
+}
+
+#[test]
+fn test_load_policy_maps_current_format() {
+    let path = temp_path("load_policy.bin");
+    trained_agent(StorageKind::Dense).save(&path).unwrap();
+
+    let policy = load_policy(&path).unwrap();
+    let best = policy.best_action(0);
+    drop(policy);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(best, Action::Right);
+}
+
+#[test]
+fn test_load_policy_opens_saved_double_q_agent() {
+    let path = temp_path("double_q_policy.bin");
+    let mut agent = trained_agent(StorageKind::Dense);
+    Learner::DoubleQ.prepare(&mut agent);
+    agent.q2.as_mut().unwrap()[500] = [4.0, 0.0, 0.0];
+    agent.save(&path).unwrap();
+
+    let policy = load_policy(&path).unwrap();
+    let values = policy.q_values(500);
+    drop(policy);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(values, agent.q_values(500));
+}
+
+#[test]
+fn test_mapped_policy_rejects_non_finite_value() {
+    let path = temp_path("mapped_nan.bin");
+    let mut agent = trained_agent(StorageKind::Dense);
+    agent.q[500][2] = f64::NAN;
+    agent.save(&path).unwrap();
+
+    let result = MappedPolicy::open(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(matches!(
+        result,
+        Err(GameError::NonFiniteValue(ref what)) if what == "Q-value in state 500"
+    ));
+}
+
+#[test]
+fn test_mapped_policy_rejects_unsorted_sparse_entries() {
+    let path = temp_path("mapped_unsorted.bin");
+    let mut bytes = Vec::new();
+    trained_agent(StorageKind::Sparse)
+        .save_to(&mut bytes)
+        .unwrap();
+
+    // Swap the first two entries, then fix the checksum so only the order is wrong
+    let table = bytes.windows(4).position(|tag| tag == b"QTAB").unwrap() + 12 + 8;
+    let entry = 8 + 8 * NUM_ACTIONS;
+    let (first, rest) = bytes[table..].split_at_mut(entry);
+    first.swap_with_slice(&mut rest[..entry]);
+    let end = bytes.len() - 16;
+    let checksum = crc32fast::hash(&bytes[..end]);
+    bytes[end + 12..].copy_from_slice(&checksum.to_le_bytes());
+    std::fs::write(&path, &bytes).unwrap();
+
+    let result = MappedPolicy::open(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(matches!(result, Err(GameError::InvalidAgentData(_))));
+}
@@ -0,0 +1,85 @@
+use textdrive::game::{Game, COLS_COUNT};
+use textdrive::postmortem::*;
+
+/// Plays a game without steering until the car hits a wall
+fn record_crash(replay: &mut Replay) {
+    let mut game = Game::new();
+    for _ in 0..1000 {
+        game.scroll_course();
+        replay.record(&game, None);
+        if game.has_collision() {
+            return;
+        }
+    }
+    panic!("car never crashed");
+}
+
+#[test]
+fn test_replay_record_drops_oldest() {
+    let mut replay = Replay::new(2);
+    let mut game = Game::new();
+    for _ in 0..3 {
+        game.scroll_course();
+        replay.record(&game, Some([1.0, 2.0, 3.0]));
+    }
+
+    assert_eq!(replay.len(), 2);
+    assert_eq!(replay.frame(0).unwrap().game.distance, 2);
+    assert_eq!(replay.frame(1).unwrap().q_values, Some([1.0, 2.0, 3.0]));
+}
+
+#[test]
+fn test_replay_truncate_to() {
+    let mut replay = Replay::new(10);
+    let mut game = Game::new();
+    for _ in 0..5 {
+        game.scroll_course();
+        replay.record(&game, None);
+    }
+
+    replay.truncate_to(3);
+
+    assert_eq!(replay.len(), 3);
+    assert_eq!(replay.frame(2).unwrap().game.distance, 3);
+}
+
+#[test]
+fn test_point_of_no_return_without_crash() {
+    let mut replay = Replay::new(10);
+    let mut game = Game::new();
+    game.scroll_course();
+    replay.record(&game, None);
+
+    assert_eq!(replay.point_of_no_return(1), None);
+}
+
+#[test]
+fn test_point_of_no_return_is_before_crash() {
+    let mut replay = Replay::new(1000);
+    record_crash(&mut replay);
+
+    let tick = replay.point_of_no_return(1).unwrap();
+    assert!(tick < replay.len());
+}
+
+#[test]
+fn test_point_of_no_return_with_unlimited_moves() {
+    let mut replay = Replay::new(1000);
+    record_crash(&mut replay);
+
+    // Every row has a gap, so a car that can jump anywhere is only doomed on
+    // the crash frame itself
+    assert_eq!(
+        replay.point_of_no_return(COLS_COUNT),
+        Some(replay.len() - 1)
+    );
+}
+
+#[test]
+fn test_point_of_no_return_single_crash_frame() {
+    let mut replay = Replay::new(1000);
+    record_crash(&mut replay);
+    let crash = replay.frame(replay.len() - 1).unwrap().game.clone();
+
+    assert_eq!(find_point_of_no_return(&[&crash], 1), Some(0));
+}
@@ -1,5 +1,13 @@
+use textdrive::config::TrainConfig;
 use textdrive::game::Game;
+use textdrive::hyperparams;
+use textdrive::learner::Learner;
+use textdrive::model::{read_checkpoint, write_checkpoint};
+use textdrive::policy::Policy;
 use textdrive::qlearning::*;
+use textdrive::rng::SimRng;
+use textdrive::traces::CreditAssignment;
+use textdrive::training::train_episode;
 
 #[test]
 fn test_action_from_usize() {
@@ -84,3 +92,87 @@ fn test_update_q() {
     // Q-value should have changed
     assert_ne!(agent.q[state][action as usize], initial_q);
 }
+
+#[test]
+fn test_agent_step_schedule() {
+    let params = hyperparams::Hyperparams {
+        epsilon: hyperparams::Schedule::exponential(1.0, 0.5)
+            .with_min(0.1)
+            .per(hyperparams::DecayUnit::Step),
+        ..Default::default()
+    };
+    let mut agent = Agent::with_params(params);
+
+    agent.decay_epsilon();
+    assert_eq!(agent.epsilon, 1.0);
+
+    agent.advance_step();
+    assert_eq!(agent.epsilon, 0.5);
+
+    for _ in 0..10 {
+        agent.advance_step();
+    }
+    assert_eq!(agent.epsilon, 0.1);
+}
+
+#[test]
+fn test_greedy_choice_matches_policy() {
+    let mut agent = Agent::new();
+    agent.epsilon = 0.0;
+    agent.q[7] = [0.1, 0.5, 0.2];
+
+    assert_eq!(choose_action(&mut agent, 7), Action::Stay);
+    assert_eq!(Policy::best_action(&agent, 7), Action::Stay);
+}
+
+fn run_training(agent: &mut Agent, courses: &mut SimRng, episodes: usize) {
+    let mut credit = CreditAssignment::OneStep;
+    for _ in 0..episodes {
+        train_episode(agent, Learner::DoubleQ, &mut credit, courses, 200);
+    }
+}
+
+fn seeded_agent(seed: u64) -> (Agent, SimRng) {
+    let mut agent = Agent::new();
+    agent.seed(seed);
+    Learner::DoubleQ.prepare(&mut agent);
+    agent.track_visits();
+    (agent, SimRng::new(seed + 1))
+}
+
+fn train_seeded(seed: u64) -> Agent {
+    let (mut agent, mut courses) = seeded_agent(seed);
+    run_training(&mut agent, &mut courses, 20);
+    agent
+}
+
+#[test]
+fn test_same_seed_trains_identical_tables() {
+    let first = train_seeded(42);
+    let second = train_seeded(42);
+    let other = train_seeded(43);
+
+    assert_eq!(first.q, second.q);
+    assert_eq!(first.q2, second.q2);
+    assert_ne!(first.q, other.q);
+}
+
+#[test]
+fn test_resumed_training_matches_uninterrupted() {
+    let (mut agent, mut courses) = seeded_agent(7);
+    run_training(&mut agent, &mut courses, 10);
+    let mut bytes = Vec::new();
+    let mut config = TrainConfig::default();
+    config.set("learner", "double-q").unwrap();
+    write_checkpoint(&agent, &courses, &config.resume_settings(), &mut bytes).unwrap();
+
+    let (mut resumed, mut resumed_courses) = read_checkpoint(&bytes).unwrap();
+    assert_eq!(resumed.episodes, 10);
+    run_training(&mut resumed, &mut resumed_courses, 10);
+    let uninterrupted = train_seeded(7);
+
+    assert_eq!(resumed.q, uninterrupted.q);
+    assert_eq!(resumed.q2, uninterrupted.q2);
+    assert_eq!(resumed.epsilon, uninterrupted.epsilon);
+    assert_eq!(resumed.visits, uninterrupted.visits);
+}
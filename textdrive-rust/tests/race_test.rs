@@ -0,0 +1,36 @@
+use textdrive::game::Game;
+use textdrive::race::*;
+
+#[test]
+fn test_leader_after_a_crash() {
+    // Both cars race the same course until the first one stops steering
+    let mut crashed = Game::with_seed(5);
+    let mut racing = crashed.clone();
+    while !crashed.has_collision() {
+        crashed.scroll_course();
+        racing.scroll_course();
+    }
+    // The crashed car drops out while the other keeps going
+    for _ in 0..3 {
+        racing.scroll_course();
+    }
+
+    let distances = [crashed.distance, racing.distance];
+    assert_eq!(leader(&distances), Some((1, 3)));
+}
+
+#[test]
+fn test_leader_dead_heat() {
+    assert_eq!(leader(&[12, 12]), None);
+    assert_eq!(leader(&[12, 12, 4]), None);
+    assert_eq!(leader(&[8]), None);
+    assert_eq!(leader(&[4, 12, 9]), Some((1, 3)));
+}
+
+#[test]
+fn test_round_winners() {
+    assert_eq!(round_winners(&[30, 18]), vec![0]);
+    assert_eq!(round_winners(&[18, 30, 30]), vec![1, 2]);
+    // Crashing together is a draw
+    assert!(round_winners(&[30, 30]).is_empty());
+}
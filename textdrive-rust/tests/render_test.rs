@@ -0,0 +1,34 @@
+use textdrive::game::{Game, COLS_COUNT, PLAYER_ROW};
+use textdrive::render::{get_cell_char, sparkline};
+
+#[test]
+fn test_sparkline_scales_to_range() {
+    assert_eq!(sparkline(&[0.0, 1.0, 2.0, 7.0], 10), "▁▂▃█");
+    assert_eq!(sparkline(&[10.0, 80.0], 10), "▁█");
+}
+
+#[test]
+fn test_sparkline_keeps_recent_values() {
+    let values: Vec<f64> = (0..100).map(f64::from).collect();
+    let line = sparkline(&values, 8);
+
+    assert_eq!(line.chars().count(), 8);
+    assert!(line.starts_with('▁'));
+    assert!(line.ends_with('█'));
+}
+
+#[test]
+fn test_sparkline_edge_cases() {
+    assert_eq!(sparkline(&[], 5), "");
+    assert_eq!(sparkline(&[3.0, 3.0, 3.0], 5), "▁▁▁");
+    assert_eq!(sparkline(&[0.0, f64::NAN, 7.0], 5), "▁ █");
+}
+
+#[test]
+fn test_cell_chars() {
+    let game = Game::with_seed(1);
+    let empty = (0..COLS_COUNT).find(|&x| x != game.player_x).unwrap();
+
+    assert_eq!(get_cell_char(&game, game.player_x, PLAYER_ROW), "車");
+    assert_eq!(get_cell_char(&game, empty, PLAYER_ROW), "  ");
+}
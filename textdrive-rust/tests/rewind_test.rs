@@ -0,0 +1,80 @@
+use textdrive::game::Game;
+use textdrive::rewind::*;
+
+fn game_at(distance: i32) -> Game {
+    let mut game = Game::new();
+    game.distance = distance;
+    game
+}
+
+#[test]
+fn test_rewind_record_drops_oldest() {
+    let mut rewind = Rewind::new(3, 1);
+    for distance in 0..5 {
+        rewind.record(&game_at(distance));
+    }
+
+    assert_eq!(rewind.len(), 3);
+    assert!(rewind.begin(false));
+    rewind.step_back();
+    rewind.step_back();
+    assert!(!rewind.step_back());
+    assert_eq!(rewind.preview().unwrap().distance, 2);
+}
+
+#[test]
+fn test_rewind_commit_consumes_charge() {
+    let mut rewind = Rewind::new(10, 1);
+    for distance in 0..5 {
+        rewind.record(&game_at(distance));
+    }
+
+    assert!(rewind.begin(false));
+    rewind.step_back();
+    rewind.step_back();
+    assert_eq!(rewind.offset(), 2);
+
+    let game = rewind.commit().unwrap();
+    assert_eq!(game.distance, 2);
+    assert_eq!(rewind.len(), 3);
+    assert_eq!(rewind.charges(), 0);
+    assert!(!rewind.is_scrubbing());
+
+    // No charges left
+    assert!(!rewind.begin(false));
+}
+
+#[test]
+fn test_rewind_free_does_not_consume_charge() {
+    let mut rewind = Rewind::new(10, 0);
+    rewind.record(&game_at(7));
+
+    assert!(rewind.begin(true));
+    assert_eq!(rewind.commit().unwrap().distance, 7);
+    assert_eq!(rewind.charges(), 0);
+}
+
+#[test]
+fn test_rewind_cancel_keeps_history() {
+    let mut rewind = Rewind::new(10, 2);
+    for distance in 0..4 {
+        rewind.record(&game_at(distance));
+    }
+
+    rewind.begin(false);
+    rewind.step_back();
+    assert!(rewind.step_forward());
+    assert!(!rewind.step_forward());
+    rewind.cancel();
+
+    assert!(rewind.preview().is_none());
+    assert_eq!(rewind.len(), 4);
+    assert_eq!(rewind.charges(), 2);
+}
+
+#[test]
+fn test_rewind_begin_empty() {
+    let mut rewind = Rewind::default();
+    assert!(rewind.is_empty());
+    assert!(!rewind.begin(true));
+}
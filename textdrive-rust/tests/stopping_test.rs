@@ -0,0 +1,45 @@
+use textdrive::stopping::*;
+
+#[test]
+fn test_target_score() {
+    let mut stopping = EarlyStopping::default();
+    stopping.target_score = Some(500.0);
+
+    assert_eq!(stopping.evaluated(499.0), None);
+    assert_eq!(stopping.evaluated(500.0), Some(StopReason::TargetScore));
+}
+
+#[test]
+fn test_patience_counts_evaluations_without_a_new_best() {
+    let mut stopping = EarlyStopping::default();
+    stopping.patience = Some(2);
+
+    assert_eq!(stopping.evaluated(100.0), None);
+    assert_eq!(stopping.evaluated(90.0), None);
+    assert_eq!(stopping.evaluated(120.0), None);
+    assert_eq!(stopping.evaluated(120.0), None);
+    assert_eq!(stopping.evaluated(110.0), Some(StopReason::NoImprovement));
+}
+
+#[test]
+fn test_q_change_threshold() {
+    let mut stopping = EarlyStopping::default();
+    assert_eq!(stopping.q_changed(0.0), None);
+
+    stopping.min_q_change = Some(0.01);
+    assert_eq!(stopping.q_changed(0.5), None);
+    assert_eq!(stopping.q_changed(0.001), Some(StopReason::Converged));
+}
+
+#[test]
+fn test_stop_reason_round_trip() {
+    for reason in [
+        StopReason::EpisodeLimit,
+        StopReason::TargetScore,
+        StopReason::NoImprovement,
+        StopReason::Converged,
+    ] {
+        assert_eq!(reason.to_string().parse::<StopReason>().unwrap(), reason);
+    }
+    assert!("bored".parse::<StopReason>().is_err());
+}
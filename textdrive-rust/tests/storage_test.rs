@@ -0,0 +1,95 @@
+mod common;
+
+use common::temp_path;
+use textdrive::encoder::Encoder;
+use textdrive::hyperparams::Hyperparams;
+use textdrive::qlearning::{update_q, Action, Agent, STATE_SIZE};
+use textdrive::storage::*;
+
+#[test]
+fn test_dense_and_sparse_read_the_same() {
+    for kind in [StorageKind::Dense, StorageKind::Sparse] {
+        let mut table = QTable::new(kind, 10);
+        table[3][1] = 2.5;
+
+        assert_eq!(table.kind(), kind);
+        assert_eq!(table.len(), 10);
+        assert_eq!(table[3], [0.0, 2.5, 0.0]);
+        assert_eq!(table[4], [0.0; 3]);
+    }
+}
+
+#[test]
+fn test_sparse_only_stores_written_states() {
+    let mut table = QTable::new(StorageKind::Sparse, 1 << 30);
+    let _ = table[12345];
+    table[7][0] = 1.0;
+    table[2][2] = -1.0;
+
+    assert_eq!(table.stored(), 2);
+    assert_eq!(table.stored_states(), vec![2, 7]);
+}
+
+#[test]
+#[should_panic]
+fn test_sparse_rejects_out_of_range_state() {
+    let table = QTable::new(StorageKind::Sparse, 4);
+    let _ = table[4];
+}
+
+#[test]
+fn test_storage_kind_parse() {
+    assert_eq!("dense".parse::<StorageKind>().unwrap(), StorageKind::Dense);
+    assert_eq!(
+        "sparse".parse::<StorageKind>().unwrap(),
+        StorageKind::Sparse
+    );
+    assert!("mmap".parse::<StorageKind>().is_err());
+}
+
+#[test]
+fn test_agent_fits_small_thread_stack() {
+    // The table lives on the heap, so creating an agent needs little stack
+    let handle = std::thread::Builder::new()
+        .stack_size(64 * 1024)
+        .spawn(|| Agent::new().q.len())
+        .unwrap();
+
+    assert_eq!(handle.join().unwrap(), STATE_SIZE);
+}
+
+#[test]
+fn test_sparse_agent_save_load() {
+    let path = temp_path("sparse.bin");
+    let mut agent = Agent::with_storage(
+        Encoder::default(),
+        Hyperparams::default(),
+        StorageKind::Sparse,
+    );
+    update_q(&mut agent, 100, Action::Right, -100.0, 101);
+    agent.best_score = 17;
+    agent.save(&path).unwrap();
+
+    let size = std::fs::metadata(&path).unwrap().len();
+    let loaded = Agent::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(size < 1024);
+    assert_eq!(loaded.q.kind(), StorageKind::Sparse);
+    assert_eq!(loaded.q.stored(), 1);
+    assert_eq!(loaded.q[100], agent.q[100]);
+    assert_eq!(loaded.best_score, 17);
+}
+
+#[test]
+fn test_max_abs_difference() {
+    let mut dense = QTable::new(StorageKind::Dense, 8);
+    let mut sparse = QTable::new(StorageKind::Sparse, 8);
+    assert_eq!(dense.max_abs_difference(&sparse), 0.0);
+
+    dense[2] = [1.0, 0.5, 0.0];
+    sparse[2] = [1.0, 0.0, 0.0];
+    sparse[5] = [0.0, -2.0, 0.0];
+    assert_eq!(dense.max_abs_difference(&sparse), 2.0);
+    assert_eq!(sparse.max_abs_difference(&dense), 2.0);
+}
@@ -0,0 +1,98 @@
+use textdrive::encoder::Encoder;
+use textdrive::evaluation::Evaluation;
+use textdrive::hyperparams::{Hyperparams, Schedule};
+use textdrive::rng::SimRng;
+use textdrive::sweep::*;
+
+fn base_trial() -> Trial {
+    Trial {
+        params: Hyperparams::default(),
+        encoder: Encoder::default(),
+        label: String::new(),
+    }
+}
+
+#[test]
+fn test_axis_parsing() {
+    assert_eq!(
+        Axis::<f64>::parse("gamma", "0.9, 0.99").unwrap(),
+        Axis::List(vec![0.9, 0.99])
+    );
+    assert_eq!(
+        Axis::<f64>::parse("gamma", "0.5..0.9").unwrap(),
+        Axis::Range(0.5, 0.9)
+    );
+    assert_eq!(
+        Axis::<Schedule>::parse("alpha", "0.1").unwrap(),
+        Axis::List(vec![Schedule::constant(0.1)])
+    );
+    assert!(Axis::<f64>::parse("gamma", "0.9..0.5").is_err());
+    assert!(Axis::<f64>::parse("gamma", "high").is_err());
+    assert!(Axis::<Encoder>::parse("encoder", "1..3").is_err());
+}
+
+#[test]
+fn test_grid_covers_every_combination() {
+    let mut sweep = SweepConfig::default();
+    sweep.set("alpha", "0.1,0.2").unwrap();
+    sweep.set("gamma", "0.5..0.9").unwrap();
+    sweep.set("grid-points", "3").unwrap();
+
+    let trials = sweep.trials(&base_trial(), &mut SimRng::new(1));
+
+    assert_eq!(trials.len(), 6);
+    assert_eq!(trials[0].label, "alpha=0.1 gamma=0.5");
+    assert_eq!(trials[5].label, "alpha=0.2 gamma=0.9");
+    assert_eq!(trials[4].params.gamma, 0.7);
+    assert_eq!(trials[4].params.alpha, Schedule::constant(0.2));
+    assert_eq!(
+        trials[4].params.collision_reward,
+        Hyperparams::default().collision_reward
+    );
+}
+
+#[test]
+fn test_random_search_is_seeded() {
+    let mut sweep = SweepConfig::default();
+    sweep.set("search", "random").unwrap();
+    sweep.set("trials", "5").unwrap();
+    sweep.set("gamma", "0.5..0.9").unwrap();
+    sweep.set("encoder", "window,gap").unwrap();
+
+    let first = sweep.trials(&base_trial(), &mut SimRng::new(3));
+    let second = sweep.trials(&base_trial(), &mut SimRng::new(3));
+
+    assert_eq!(first.len(), 5);
+    assert_eq!(first, second);
+    assert!(first
+        .iter()
+        .all(|trial| (0.5..=0.9).contains(&trial.params.gamma)));
+}
+
+#[test]
+fn test_rank_orders_configurations() {
+    let runs = vec![
+        (0, Evaluation::new(vec![10, 20], 2)),
+        (1, Evaluation::new(vec![100, 100], 0)),
+        (0, Evaluation::new(vec![30, 40], 1)),
+    ];
+
+    let rankings = rank(&runs);
+
+    assert_eq!(rankings.len(), 2);
+    assert_eq!(rankings[0].trial, 1);
+    assert_eq!(rankings[0].std_dev(), 0.0);
+    assert_eq!(rankings[1].means, vec![15.0, 35.0]);
+    assert_eq!(rankings[1].mean(), 25.0);
+    assert_eq!(rankings[1].crash_rate, 0.75);
+    assert!((rankings[1].std_dev() - 200f64.sqrt()).abs() < 1e-9);
+}
+
+#[test]
+fn test_invalid_sweep_settings() {
+    let mut sweep = SweepConfig::default();
+
+    assert!(sweep.set("seeds", "0").is_err());
+    assert!(sweep.set("search", "bayesian").is_err());
+    assert!(sweep.set("episodes", "10").is_err());
+}
@@ -0,0 +1,126 @@
+use textdrive::learner::{Learner, Transition};
+use textdrive::qlearning::{Action, Agent};
+use textdrive::traces::*;
+
+fn step(state: usize, reward: f64, next_action: Action) -> Transition {
+    Transition {
+        state,
+        action: Action::Stay,
+        reward,
+        next_state: state + 1,
+        next_action,
+    }
+}
+
+#[test]
+fn test_one_step_buffer_matches_learner() {
+    let mut agent = Agent::new();
+    agent.q[1] = [0.0, 4.0, 0.0];
+    let mut buffer = NStepBuffer::new(1);
+
+    buffer.push(&mut agent, Learner::QLearning, &step(0, 1.0, Action::Stay));
+
+    let expected = agent.alpha * (1.0 + agent.params.gamma * 4.0);
+    assert!((agent.q[0][Action::Stay as usize] - expected).abs() < 1e-12);
+}
+
+#[test]
+fn test_n_step_propagates_reward() {
+    let mut agent = Agent::new();
+    let mut buffer = NStepBuffer::new(3);
+
+    buffer.push(&mut agent, Learner::QLearning, &step(0, 0.0, Action::Stay));
+    buffer.push(&mut agent, Learner::QLearning, &step(1, 0.0, Action::Stay));
+    assert_eq!(agent.q[0][Action::Stay as usize], 0.0);
+
+    buffer.push(
+        &mut agent,
+        Learner::QLearning,
+        &step(2, -100.0, Action::Stay),
+    );
+
+    // The penalty reaches the state three steps back in one update
+    let gamma = agent.params.gamma;
+    let expected = agent.alpha * gamma * gamma * -100.0;
+    assert!((agent.q[0][Action::Stay as usize] - expected).abs() < 1e-9);
+}
+
+#[test]
+fn test_n_step_flush_updates_remaining() {
+    let mut agent = Agent::new();
+    let mut buffer = NStepBuffer::new(5);
+
+    buffer.push(&mut agent, Learner::Sarsa, &step(0, 1.0, Action::Stay));
+    let last = step(1, -100.0, Action::Stay);
+    buffer.push(&mut agent, Learner::Sarsa, &last);
+    buffer.flush(
+        &mut agent,
+        Learner::Sarsa,
+        last.next_state,
+        last.next_action,
+    );
+
+    assert!(agent.q[0][Action::Stay as usize] < 0.0);
+    assert_eq!(agent.q[1][Action::Stay as usize], agent.alpha * -100.0);
+}
+
+#[test]
+fn test_watkins_traces_cut_after_exploration() {
+    let mut agent = Agent::new();
+    agent.q[2] = [0.0, 1.0, 0.0];
+    let mut traces = EligibilityTraces::new(0.9, TraceKind::Watkins);
+
+    traces.update(&mut agent, &step(0, 0.0, Action::Stay));
+    assert_eq!(traces.len(), 1);
+
+    // Left isn't greedy in state 2, so all traces are cut
+    traces.update(&mut agent, &step(1, 0.0, Action::Left));
+    assert!(traces.is_empty());
+}
+
+#[test]
+fn test_watkins_traces_propagate_penalty() {
+    let mut agent = Agent::new();
+    let mut traces = EligibilityTraces::new(0.9, TraceKind::Watkins);
+
+    traces.update(&mut agent, &step(0, 0.0, Action::Stay));
+    traces.update(&mut agent, &step(1, -100.0, Action::Stay));
+
+    assert!(agent.q[0][Action::Stay as usize] < 0.0);
+    assert!(agent.q[1][Action::Stay as usize] < agent.q[0][Action::Stay as usize]);
+}
+
+#[test]
+fn test_peng_traces_survive_exploration() {
+    let mut agent = Agent::new();
+    agent.q[2] = [0.0, 1.0, 0.0];
+    let mut traces = EligibilityTraces::new(0.9, TraceKind::Peng);
+
+    traces.update(&mut agent, &step(0, 0.0, Action::Stay));
+    traces.update(&mut agent, &step(1, 0.0, Action::Left));
+
+    assert_eq!(traces.len(), 2);
+    let expected = agent.params.gamma * 0.9;
+    assert!((traces.get(0, Action::Stay) - expected).abs() < 1e-12);
+}
+
+#[test]
+fn test_credit_assignment_clears_traces() {
+    let mut agent = Agent::new();
+    let mut credit = CreditAssignment::Lambda(EligibilityTraces::new(0.5, TraceKind::Peng));
+
+    credit.update(&mut agent, Learner::QLearning, &step(0, 1.0, Action::Stay));
+    credit.start_episode();
+
+    match credit {
+        CreditAssignment::Lambda(ref traces) => assert!(traces.is_empty()),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn test_trace_kind_from_str() {
+    assert_eq!("watkins".parse::<TraceKind>().unwrap(), TraceKind::Watkins);
+    assert_eq!("peng".parse::<TraceKind>().unwrap(), TraceKind::Peng);
+    assert!("sutton".parse::<TraceKind>().is_err());
+}
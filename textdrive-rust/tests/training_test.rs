@@ -0,0 +1,84 @@
+use textdrive::learner::Learner;
+use textdrive::qlearning::Agent;
+use textdrive::rng::SimRng;
+use textdrive::traces::CreditAssignment;
+use textdrive::training::*;
+
+fn seeded_agent(seed: u64) -> Agent {
+    let mut agent = Agent::new();
+    agent.seed(seed);
+    agent.track_visits();
+    agent
+}
+
+#[test]
+fn test_episode_stops_at_step_cap() {
+    let mut agent = seeded_agent(1);
+    let metrics = run_episode(
+        &mut agent,
+        Learner::QLearning,
+        &mut CreditAssignment::OneStep,
+        3,
+        5,
+    );
+
+    assert!(metrics.steps <= 5);
+    assert_eq!(agent.clock.steps, metrics.steps as u64);
+    assert_eq!(agent.episodes, 1);
+    assert_eq!(agent.best_score, metrics.distance);
+    let visits: u64 = agent.visits.as_ref().unwrap().values().sum();
+    assert_eq!(visits, metrics.steps as u64);
+}
+
+#[test]
+fn test_episode_learns_from_its_steps() {
+    let mut agent = seeded_agent(2);
+    let metrics = run_episode(
+        &mut agent,
+        Learner::QLearning,
+        &mut CreditAssignment::OneStep,
+        9,
+        200,
+    );
+
+    assert!(metrics.steps > 0);
+    assert!(metrics.mean_abs_td_error > 0.0);
+    assert_ne!(agent.q, Agent::new().q);
+}
+
+#[test]
+fn test_train_episode_reports_schedule_then_decays() {
+    let mut agent = seeded_agent(3);
+    let mut courses = SimRng::new(4);
+    let epsilon = agent.epsilon;
+
+    let metrics = train_episode(
+        &mut agent,
+        Learner::Sarsa,
+        &mut CreditAssignment::OneStep,
+        &mut courses,
+        200,
+    );
+
+    assert_eq!(metrics.epsilon, epsilon);
+    assert_eq!(metrics.alpha, agent.alpha);
+    assert_eq!(agent.clock.episodes, 1);
+    assert!(agent.epsilon < epsilon);
+}
+
+#[test]
+fn test_same_course_and_seed_give_same_episode() {
+    let run = |seed| {
+        let mut agent = seeded_agent(seed);
+        let metrics = run_episode(
+            &mut agent,
+            Learner::ExpectedSarsa,
+            &mut CreditAssignment::OneStep,
+            11,
+            200,
+        );
+        (agent.q, metrics.steps, metrics.total_reward)
+    };
+
+    assert_eq!(run(5), run(5));
+}
@@ -0,0 +1,88 @@
+use rand::Rng;
+use textdrive::encoder::{Encoder, StateEncoder};
+use textdrive::game::{Game, COLS_COUNT, ROWS_COUNT};
+use textdrive::qlearning::{do_action, Action};
+use textdrive::rng::SimRng;
+use textdrive::vec_game::{StepOutcome, VecGame};
+
+fn random_actions(rng: &mut SimRng, count: usize) -> Vec<Action> {
+    (0..count)
+        .map(|_| Action::from_usize(rng.random_range(0..3)).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_games_play_like_single_games() {
+    let encoders: Vec<Encoder> = ["window", "gap", "walls"]
+        .iter()
+        .map(|id| id.parse().unwrap())
+        .collect();
+    let mut games = VecGame::new(16, 99);
+    let mut singles: Vec<Game> = (0..games.len())
+        .map(|i| Game::with_seed(games.seed(i)))
+        .collect();
+    let mut rng = SimRng::new(5);
+    let mut outcome = StepOutcome::default();
+    let mut states = Vec::new();
+    let mut crashes = 0;
+
+    for _ in 0..2000 {
+        let actions = random_actions(&mut rng, games.len());
+        games.step(&actions, &mut outcome);
+
+        for (i, game) in singles.iter_mut().enumerate() {
+            do_action(game, actions[i]);
+            game.scroll_course();
+            assert_eq!(outcome.crashed[i], game.has_collision());
+            assert_eq!(outcome.distances[i], game.distance);
+            if outcome.crashed[i] {
+                crashes += 1;
+                *game = Game::with_seed(games.seed(i));
+            }
+        }
+        for encoder in &encoders {
+            games.observe(encoder, &mut states);
+            for (i, game) in singles.iter().enumerate() {
+                assert_eq!(states[i], encoder.encode(game));
+            }
+        }
+    }
+    assert!(crashes > 0);
+}
+
+#[test]
+fn test_board_matches_game_cells() {
+    let mut games = VecGame::with_seeds(&[1, 2]);
+    let mut game = Game::with_seed(2);
+    let mut outcome = StepOutcome::default();
+    for _ in 0..ROWS_COUNT + 3 {
+        games.step(&[Action::Stay, Action::Stay], &mut outcome);
+        game.scroll_course();
+    }
+
+    let board = games.board(1);
+    assert_eq!(board.player_x, game.player_x);
+    for y in 0..ROWS_COUNT {
+        for x in 0..COLS_COUNT {
+            assert_eq!(board.get_cell(x, y), game.get_cell(x, y));
+        }
+    }
+}
+
+#[test]
+fn test_reset_starts_course_over() {
+    let mut games = VecGame::with_seeds(&[7]);
+    let mut outcome = StepOutcome::default();
+    for _ in 0..5 {
+        games.step(&[Action::Right], &mut outcome);
+    }
+    games.reset(0, 8);
+
+    assert_eq!(games.seed(0), 8);
+    assert_eq!(games.distance(0), 0);
+    assert_eq!(games.board(0).player_x, COLS_COUNT / 2);
+    let fresh = Game::with_seed(8);
+    let board = games.board(0);
+    assert!((0..ROWS_COUNT)
+        .all(|y| (0..COLS_COUNT).all(|x| board.get_cell(x, y) == fresh.get_cell(x, y))));
+}